@@ -0,0 +1,249 @@
+use soloud::LoadExt;
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+const CHIME: &[u8] = include_bytes!("../resources/chimes.wav");
+
+/// Events that play a sound, each of which can have its own file.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sound {
+    BreakDue,
+    BreakOver,
+    Exercise,
+    Goal,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 4] = [
+        Sound::BreakDue,
+        Sound::BreakOver,
+        Sound::Exercise,
+        Sound::Goal,
+    ];
+}
+
+/// A sound file that could not be loaded, and why.
+#[derive(Debug)]
+pub struct SoundError(PathBuf, String);
+
+impl fmt::Display for SoundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to load sound `{}`: {}", self.0.display(), self.1)
+    }
+}
+
+enum Command {
+    Play {
+        sound: Sound,
+        volume: f32,
+        times: u32,
+    },
+    Stop,
+}
+
+/// Handle to the audio thread, which owns the soloud instance so that playing
+/// a sound never blocks the UI loop. Without an audio device there is no
+/// thread and sounds are dropped.
+pub struct Audio {
+    sender: Option<Sender<Command>>,
+}
+
+impl Audio {
+    /// Starts the audio thread with the sounds set in `config`. Sounds that
+    /// fail to load are replaced by the built-in chime and returned as errors
+    /// alongside the handle. When no audio device can be opened, the handle
+    /// is silent.
+    pub fn new(config: &Config) -> (Audio, Vec<SoundError>) {
+        let (sender, receiver) = mpsc::channel();
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let paths: Vec<Option<PathBuf>> = Sound::ALL
+            .iter()
+            .map(|sound| config.sound_file(*sound).map(Path::to_path_buf))
+            .collect();
+
+        thread::spawn(move || {
+            let soloud = match soloud::Soloud::default() {
+                Ok(soloud) => soloud,
+                Err(_) => return,
+            };
+
+            let mut chime = soloud::audio::Wav::default();
+            if chime.load_mem(CHIME).is_err() {
+                return;
+            }
+
+            let (wavs, chosen, errors) = load_all(chime, &paths);
+
+            let _ = ready_sender.send(errors);
+            run(soloud, wavs, chosen, receiver);
+        });
+
+        // the thread hangs up without an answer when it has no audio
+        return match ready_receiver.recv() {
+            Ok(errors) => (
+                Audio {
+                    sender: Some(sender),
+                },
+                errors,
+            ),
+            Err(_) => (Audio::silent(), Vec::new()),
+        };
+    }
+
+    /// A handle that plays nothing.
+    pub fn silent() -> Audio {
+        Audio { sender: None }
+    }
+
+    pub fn is_silent(&self) -> bool {
+        return self.sender.is_none();
+    }
+
+    pub fn play(&self, sound: Sound, volume: f32) {
+        self.repeat(sound, volume, 1);
+    }
+
+    /// Plays the sound `times` times in a row.
+    pub fn repeat(&self, sound: Sound, volume: f32, times: u32) {
+        self.send(Command::Play {
+            sound,
+            volume,
+            times,
+        });
+    }
+
+    pub fn stop(&self) {
+        self.send(Command::Stop);
+    }
+
+    fn send(&self, command: Command) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(command);
+        }
+    }
+}
+
+/// Loads the file of each sound once, even when several sounds share it.
+/// Returns the loaded sounds, with the chime first, and the index of the one
+/// to play for each event.
+fn load_all(
+    chime: soloud::audio::Wav,
+    paths: &[Option<PathBuf>],
+) -> (Vec<soloud::audio::Wav>, Vec<usize>, Vec<SoundError>) {
+    let mut wavs = vec![chime];
+    let mut loaded: Vec<(&PathBuf, usize)> = Vec::new();
+    let mut chosen = Vec::new();
+    let mut errors = Vec::new();
+
+    for path in paths {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                chosen.push(0);
+                continue;
+            }
+        };
+
+        if let Some((_, index)) = loaded.iter().find(|(loaded, _)| *loaded == path) {
+            chosen.push(*index);
+            continue;
+        }
+
+        let index = match load(path) {
+            Ok(wav) => {
+                wavs.push(wav);
+                wavs.len() - 1
+            }
+            Err(err) => {
+                errors.push(err);
+                0
+            }
+        };
+
+        loaded.push((path, index));
+        chosen.push(index);
+    }
+
+    return (wavs, chosen, errors);
+}
+
+fn load(path: &Path) -> Result<soloud::audio::Wav, SoundError> {
+    if let Err(err) = fs::metadata(path) {
+        return Err(SoundError(path.to_path_buf(), err.to_string()));
+    }
+
+    let mut wav = soloud::audio::Wav::default();
+    wav.load(path).map_err(|_| {
+        SoundError(
+            path.to_path_buf(),
+            "not a WAV, OGG, MP3 or FLAC file that can be decoded".to_string(),
+        )
+    })?;
+
+    return Ok(wav);
+}
+
+/// Serves commands until the `Audio` handle is dropped.
+fn run(
+    mut soloud: soloud::Soloud,
+    wavs: Vec<soloud::audio::Wav>,
+    chosen: Vec<usize>,
+    receiver: Receiver<Command>,
+) {
+    // sound, volume and plays left of a repeated sound, with when the next
+    // one is due
+    let mut repeat: Option<(Sound, f32, u32, Instant)> = None;
+
+    loop {
+        let command = match repeat {
+            Some((_, _, _, due)) => {
+                match receiver.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                    Ok(command) => Some(command),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            None => match receiver.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            },
+        };
+
+        let (sound, volume, times) = match (command, repeat) {
+            (
+                Some(Command::Play {
+                    sound,
+                    volume,
+                    times,
+                }),
+                _,
+            ) => (sound, volume, times),
+            (Some(Command::Stop), _) => {
+                soloud.stop_all();
+                repeat = None;
+                continue;
+            }
+            (None, Some((sound, volume, times, _))) => (sound, volume, times),
+            (None, None) => continue,
+        };
+
+        let wav = &wavs[chosen[sound as usize]];
+
+        let handle = soloud.play(wav);
+        soloud.set_volume(handle, volume);
+
+        let gap = Duration::from_secs_f64(wav.length().max(0.1));
+
+        repeat = match times {
+            0 | 1 => None,
+            _ => Some((sound, volume, times - 1, Instant::now() + gap)),
+        };
+    }
+}
@@ -0,0 +1,504 @@
+use chrono::NaiveDate;
+
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::duration;
+use crate::export::Format;
+use crate::ipc;
+use crate::status;
+
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "\
+Usage: stretchtime [COMMAND] [OPTIONS] [INTERVAL]
+
+Reminds you to stretch every INTERVAL (e.g. 600, 90s, 25m, 1h30m, 00:45:00).
+
+Commands:
+  run         Start the timer (default)
+  status      Show the state of the running timer
+  pause       Pause the running timer
+  resume      Resume the running timer
+  reset       Restart the work interval of the running timer
+  snooze      Put off the break that is due
+  skip        Move on to the next phase
+  set-interval <DURATION>
+              Change the interval of the running timer
+  toggle-auto Toggle auto mode of the running timer
+  acknowledge Stop the running timer repeating its alert
+  stats       Show break statistics
+  history     Show the break history
+  export      Write the break history to a file or standard output
+
+Options for run:
+  -i, --interval <DURATION>   Time between breaks
+  -b, --break <DURATION>      Length of each break
+      --volume <VOLUME>       Chime volume, from 0.0 to 1.0
+      --sound <FILE>          Play FILE instead of the built-in chime
+  -a, --auto                  Start with auto mode enabled, moving
+                              between work and breaks automatically
+      --max-pause <DURATION>  Resume automatically after pausing this long
+  -d, --daemon                Run in the background without the terminal
+                              interface
+
+Options for status:
+  -f, --format <FORMAT>       text (default), json, waybar, or a template
+                              such as \"{title} {hhmmss}\"
+      --follow                Keep printing the status as it changes
+
+Options for export:
+  -f, --format <FORMAT>       csv (default), json or ics
+      --from <DATE>           Only events on or after DATE (YYYY-MM-DD)
+      --to <DATE>             Only events on or before DATE (YYYY-MM-DD)
+  -o, --output <FILE>         Write to FILE instead of standard output
+
+  -c, --config <FILE>         Read settings from FILE instead of
+                              $XDG_CONFIG_HOME/stretchtime/config.toml
+  -h, --help                  Print this help
+  -V, --version               Print the version
+";
+
+#[derive(Default)]
+pub struct Options {
+    pub interval: Option<Duration>,
+    pub break_length: Option<Duration>,
+    pub volume: Option<f32>,
+    pub sound: Option<PathBuf>,
+    pub auto_mode: bool,
+    pub max_pause: Option<Duration>,
+    pub daemon: bool,
+}
+
+pub struct StatusOptions {
+    pub format: status::Format,
+    pub follow: bool,
+}
+
+pub struct ExportOptions {
+    pub format: Format,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub output: Option<PathBuf>,
+}
+
+pub struct Args {
+    pub command: Command,
+    pub config: Option<PathBuf>,
+}
+
+pub enum Command {
+    Run(Options),
+    Status(StatusOptions),
+    /// A request to the running instance.
+    Control(ipc::Command),
+    Stats,
+    History,
+    Export(ExportOptions),
+    Help,
+    Version,
+}
+
+#[derive(Debug)]
+pub enum UsageError {
+    UnknownOption(String),
+    UnknownCommand(String),
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnexpectedArgument(String),
+    MissingArgument(&'static str, &'static str),
+    WrongCommand(String, &'static str),
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UsageError::UnknownOption(option) => write!(f, "unknown option `{option}`"),
+            UsageError::UnknownCommand(command) => write!(f, "unknown command `{command}`"),
+            UsageError::MissingValue(option) => write!(f, "option `{option}` requires a value"),
+            UsageError::InvalidValue(option, reason) => {
+                write!(f, "invalid value for `{option}`: {reason}")
+            }
+            UsageError::UnexpectedArgument(argument) => {
+                write!(f, "unexpected argument `{argument}`")
+            }
+            UsageError::MissingArgument(command, argument) => {
+                write!(f, "`{command}` requires {argument}")
+            }
+            UsageError::WrongCommand(option, command) => {
+                write!(f, "`{option}` cannot be used with `{command}`")
+            }
+        }
+    }
+}
+
+pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Args, UsageError> {
+    let mut config = None;
+    let mut options = Options::default();
+    let mut status = StatusOptions {
+        format: status::Format::Text,
+        follow: false,
+    };
+    let mut export = ExportOptions {
+        format: Format::Csv,
+        from: None,
+        to: None,
+        output: None,
+    };
+    let mut command: Option<&'static str> = None;
+    let mut interval = None;
+    // parsed once the command is known, as each command has its own formats
+    let mut format: Option<(String, String)> = None;
+    // options that only apply to some commands, with those commands
+    let mut scoped: Vec<(String, &'static [&'static str])> = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+
+        let mut value = || -> Result<String, UsageError> {
+            if let Some(value) = inline_value {
+                return Ok(value.to_string());
+            }
+
+            return args
+                .next()
+                .ok_or_else(|| UsageError::MissingValue(name.clone()));
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Args::new(Command::Help, None)),
+            "-V" | "--version" => return Ok(Args::new(Command::Version, None)),
+            "-c" | "--config" => config = Some(PathBuf::from(value()?)),
+            "-i" | "--interval" => {
+                options.interval = Some(parse_duration(&name, &value()?)?);
+                scoped.push((name, &["run"]));
+            }
+            "-b" | "--break" => {
+                options.break_length = Some(parse_duration(&name, &value()?)?);
+                scoped.push((name, &["run"]));
+            }
+            "--volume" => {
+                options.volume = Some(parse_volume(&name, &value()?)?);
+                scoped.push((name, &["run"]));
+            }
+            "--sound" => {
+                options.sound = Some(PathBuf::from(value()?));
+                scoped.push((name, &["run"]));
+            }
+            "-a" | "--auto" => {
+                options.auto_mode = true;
+                scoped.push((name, &["run"]));
+            }
+            "--max-pause" => {
+                options.max_pause = Some(parse_duration(&name, &value()?)?);
+                scoped.push((name, &["run"]));
+            }
+            "-d" | "--daemon" => {
+                options.daemon = true;
+                scoped.push((name, &["run"]));
+            }
+            "-f" | "--format" => {
+                format = Some((name.clone(), value()?));
+                scoped.push((name, &["status", "export"]));
+            }
+            "--follow" => {
+                status.follow = true;
+                scoped.push((name, &["status"]));
+            }
+            "--from" => {
+                export.from = Some(parse_date(&name, &value()?)?);
+                scoped.push((name, &["export"]));
+            }
+            "--to" => {
+                export.to = Some(parse_date(&name, &value()?)?);
+                scoped.push((name, &["export"]));
+            }
+            "-o" | "--output" => {
+                export.output = Some(PathBuf::from(value()?));
+                scoped.push((name, &["export"]));
+            }
+            _ if name.starts_with('-') && name.len() > 1 && !is_negative_number(&name) => {
+                return Err(UsageError::UnknownOption(name));
+            }
+            _ if command.is_none() => {
+                command = match name.as_str() {
+                    "run" => Some("run"),
+                    "status" => Some("status"),
+                    "pause" => Some("pause"),
+                    "resume" => Some("resume"),
+                    "reset" => Some("reset"),
+                    "snooze" => Some("snooze"),
+                    "skip" => Some("skip"),
+                    "set-interval" => Some("set-interval"),
+                    "toggle-auto" => Some("toggle-auto"),
+                    "acknowledge" => Some("acknowledge"),
+                    "stats" => Some("stats"),
+                    "history" => Some("history"),
+                    "export" => Some("export"),
+                    // a word without digits is a mistyped command rather
+                    // than a bare interval, as in `stretchtime 600`
+                    _ if !name.contains(|c: char| c.is_ascii_digit()) => {
+                        return Err(UsageError::UnknownCommand(name));
+                    }
+                    // the interval was already given with `-i`
+                    _ if options.interval.is_some() => {
+                        return Err(UsageError::UnexpectedArgument(name));
+                    }
+                    _ => {
+                        options.interval = Some(parse_duration("INTERVAL", &name)?);
+                        Some("run")
+                    }
+                };
+            }
+            _ if command == Some("run") && options.interval.is_none() => {
+                options.interval = Some(parse_duration("INTERVAL", &name)?);
+            }
+            _ if command == Some("set-interval") && interval.is_none() => {
+                interval = Some(parse_duration("DURATION", &name)?);
+            }
+            _ => return Err(UsageError::UnexpectedArgument(name)),
+        }
+    }
+
+    let command = command.unwrap_or("run");
+
+    if let Some((option, _)) = scoped
+        .into_iter()
+        .find(|(_, owners)| !owners.contains(&command))
+    {
+        return Err(UsageError::WrongCommand(option, command));
+    }
+
+    if let Some((option, value)) = format {
+        match command {
+            "status" => status.format = parse_status_format(&option, &value)?,
+            _ => export.format = parse_format(&option, &value)?,
+        }
+    }
+
+    if let (Some(from), Some(to)) = (export.from, export.to) {
+        if to < from {
+            return Err(UsageError::InvalidValue(
+                "--to".to_string(),
+                format!("{to} is before {from}"),
+            ));
+        }
+    }
+
+    let command = match command {
+        "status" => Command::Status(status),
+        "pause" => Command::Control(ipc::Command::Pause),
+        "resume" => Command::Control(ipc::Command::Resume),
+        "reset" => Command::Control(ipc::Command::Reset),
+        "snooze" => Command::Control(ipc::Command::Snooze),
+        "skip" => Command::Control(ipc::Command::Skip),
+        "set-interval" => match interval {
+            Some(interval) => Command::Control(ipc::Command::SetInterval(interval)),
+            None => return Err(UsageError::MissingArgument("set-interval", "a DURATION")),
+        },
+        "toggle-auto" => Command::Control(ipc::Command::ToggleAuto),
+        "acknowledge" => Command::Control(ipc::Command::Acknowledge),
+        "stats" => Command::Stats,
+        "history" => Command::History,
+        "export" => Command::Export(export),
+        _ => Command::Run(options),
+    };
+
+    return Ok(Args::new(command, config));
+}
+
+impl Args {
+    fn new(command: Command, config: Option<PathBuf>) -> Args {
+        Args { command, config }
+    }
+}
+
+fn is_negative_number(arg: &str) -> bool {
+    return arg[1..].starts_with(|c: char| c.is_ascii_digit());
+}
+
+fn parse_duration(option: &str, value: &str) -> Result<Duration, UsageError> {
+    return duration::parse(value)
+        .map_err(|err| UsageError::InvalidValue(option.to_string(), err.to_string()));
+}
+
+fn parse_volume(option: &str, value: &str) -> Result<f32, UsageError> {
+    let invalid = || {
+        UsageError::InvalidValue(
+            option.to_string(),
+            format!("`{value}` is not a volume between 0.0 and 1.0"),
+        )
+    };
+
+    let volume: f32 = value.parse().map_err(|_| invalid())?;
+
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(invalid());
+    }
+
+    return Ok(volume);
+}
+
+fn parse_status_format(option: &str, value: &str) -> Result<status::Format, UsageError> {
+    return status::Format::parse(value).ok_or_else(|| {
+        UsageError::InvalidValue(
+            option.to_string(),
+            format!("`{value}` is not text, json, waybar or a template with {{fields}}"),
+        )
+    });
+}
+
+fn parse_format(option: &str, value: &str) -> Result<Format, UsageError> {
+    return Format::parse(value).ok_or_else(|| {
+        UsageError::InvalidValue(
+            option.to_string(),
+            format!("`{value}` is not one of csv, json or ics"),
+        )
+    });
+}
+
+fn parse_date(option: &str, value: &str) -> Result<NaiveDate, UsageError> {
+    return NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        UsageError::InvalidValue(
+            option.to_string(),
+            format!("`{value}` is not a date such as 2024-01-31"),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Args, UsageError> {
+        return parse(args.iter().map(|arg| arg.to_string()));
+    }
+
+    fn run_options(args: &[&str]) -> Options {
+        return match parse_args(args) {
+            Ok(Args {
+                command: Command::Run(options),
+                ..
+            }) => options,
+            Ok(_) => panic!("{args:?} is not a run command"),
+            Err(err) => panic!("{args:?} failed: {err}"),
+        };
+    }
+
+    #[test]
+    fn runs_by_default() {
+        let options = run_options(&[]);
+        assert_eq!(options.interval, None);
+        assert!(!options.auto_mode);
+    }
+
+    #[test]
+    fn parses_a_bare_interval() {
+        let minutes = Some(Duration::from_secs(25 * 60));
+        assert_eq!(run_options(&["25m"]).interval, minutes);
+        assert_eq!(run_options(&["run", "25m"]).interval, minutes);
+        assert_eq!(run_options(&["--auto", "25m"]).interval, minutes);
+    }
+
+    #[test]
+    fn accepts_options_before_the_command() {
+        let options = run_options(&["-i", "25m", "run"]);
+        assert_eq!(options.interval, Some(Duration::from_secs(25 * 60)));
+
+        let options = run_options(&["--interval=25m", "--auto", "run", "-b", "5m"]);
+        assert_eq!(options.interval, Some(Duration::from_secs(25 * 60)));
+        assert_eq!(options.break_length, Some(Duration::from_secs(5 * 60)));
+        assert!(options.auto_mode);
+
+        assert!(matches!(
+            parse_args(&["-c", "stretch.toml", "stats"]),
+            Ok(Args {
+                command: Command::Stats,
+                config: Some(_),
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_a_second_interval() {
+        for args in [
+            &["25m", "30m"][..],
+            &["run", "25m", "30m"],
+            &["-i", "25m", "30m"],
+            &["-i", "25m", "run", "30m"],
+        ] {
+            assert!(
+                matches!(parse_args(args), Err(UsageError::UnexpectedArgument(arg)) if arg == "30m"),
+                "{args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_options_of_another_command() {
+        assert!(matches!(
+            parse_args(&["--interval", "25m", "status"]),
+            Err(UsageError::WrongCommand(option, "status")) if option == "--interval"
+        ));
+        assert!(matches!(
+            parse_args(&["export", "--follow"]),
+            Err(UsageError::WrongCommand(option, "export")) if option == "--follow"
+        ));
+    }
+
+    #[test]
+    fn reports_unknown_commands_and_options() {
+        assert!(matches!(
+            parse_args(&["stauts"]),
+            Err(UsageError::UnknownCommand(command)) if command == "stauts"
+        ));
+        assert!(matches!(
+            parse_args(&["-i", "25m", "stauts"]),
+            Err(UsageError::UnknownCommand(command)) if command == "stauts"
+        ));
+        assert!(matches!(
+            parse_args(&["--intervall", "25m"]),
+            Err(UsageError::UnknownOption(option)) if option == "--intervall"
+        ));
+        assert!(matches!(
+            parse_args(&["5x"]),
+            Err(UsageError::InvalidValue(option, _)) if option == "INTERVAL"
+        ));
+    }
+
+    #[test]
+    fn parses_set_interval() {
+        assert!(matches!(
+            parse_args(&["set-interval", "20m"]),
+            Ok(Args {
+                command: Command::Control(ipc::Command::SetInterval(interval)),
+                ..
+            }) if interval == Duration::from_secs(20 * 60)
+        ));
+        assert!(matches!(
+            parse_args(&["set-interval"]),
+            Err(UsageError::MissingArgument("set-interval", _))
+        ));
+    }
+
+    #[test]
+    fn checks_values() {
+        assert!(matches!(
+            parse_args(&["--volume", "1.5"]),
+            Err(UsageError::InvalidValue(option, _)) if option == "--volume"
+        ));
+        assert!(matches!(
+            parse_args(&["export", "--from", "2024-02-01", "--to", "2024-01-31"]),
+            Err(UsageError::InvalidValue(option, _)) if option == "--to"
+        ));
+        assert!(matches!(
+            parse_args(&["--interval"]),
+            Err(UsageError::MissingValue(option)) if option == "--interval"
+        ));
+    }
+}
@@ -0,0 +1,355 @@
+use chrono::NaiveTime;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::audio::Sound;
+use crate::{cli, duration, notify, paths, selection, stats};
+
+#[derive(Clone, PartialEq)]
+pub struct Config {
+    pub interval: Duration,
+    pub break_length: Duration,
+    /// Number of work intervals before a long break, if cycles are enabled.
+    pub cycle_length: Option<u32>,
+    pub long_break_length: Duration,
+    /// Name of the routine to walk through during breaks. Exercises are
+    /// picked by `selection` when unset.
+    pub routine: Option<String>,
+    pub selection: selection::Settings,
+    pub goals: stats::Goals,
+    /// Time of day at which the cycle count starts over.
+    pub cycle_reset: NaiveTime,
+    pub max_pause: Option<Duration>,
+    pub snooze: Snooze,
+    /// Repeats the alert until it is acknowledged, if set.
+    pub nag: Option<Nag>,
+    pub alerts: Vec<notify::Kind>,
+    pub volume: f32,
+    /// Played for every event without a sound of its own, instead of the
+    /// built-in chime.
+    pub sound: Option<PathBuf>,
+    pub sounds: Sounds,
+    pub tick_rate: Duration,
+    pub auto_mode: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            interval: Duration::from_secs(20 * 60),
+            break_length: Duration::from_secs(5 * 60),
+            cycle_length: None,
+            long_break_length: Duration::from_secs(15 * 60),
+            routine: None,
+            selection: selection::Settings::default(),
+            goals: stats::Goals::default(),
+            cycle_reset: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+            max_pause: None,
+            snooze: Snooze::default(),
+            nag: None,
+            alerts: Vec::new(),
+            volume: 0.2,
+            sound: None,
+            sounds: Sounds::default(),
+            tick_rate: Duration::from_millis(250),
+            auto_mode: false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Snooze {
+    /// How long a break is put off.
+    #[serde(deserialize_with = "duration::deserialize")]
+    pub length: Duration,
+    /// Snoozes allowed per work interval; 0 turns snoozing off.
+    pub max: u32,
+    /// Snoozes after which alerts get louder and more insistent; 0 never
+    /// escalates.
+    pub escalate_after: u32,
+}
+
+impl Default for Snooze {
+    fn default() -> Snooze {
+        Snooze {
+            length: Duration::from_secs(5 * 60),
+            max: 3,
+            escalate_after: 2,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Nag {
+    /// Time between repeats of the alert.
+    #[serde(deserialize_with = "duration::deserialize")]
+    pub interval: Duration,
+    /// Repeats after which the alert is left alone.
+    pub max: u32,
+}
+
+impl Default for Nag {
+    fn default() -> Nag {
+        Nag {
+            interval: Duration::from_secs(60),
+            max: 5,
+        }
+    }
+}
+
+/// Sound files for each event.
+#[derive(Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sounds {
+    pub break_due: Option<PathBuf>,
+    pub break_over: Option<PathBuf>,
+    /// Played when a break moves on to the next exercise.
+    pub exercise: Option<PathBuf>,
+    /// Played when the daily goal is met.
+    pub goal: Option<PathBuf>,
+}
+
+/// The config file as written, where every key is optional.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawConfig {
+    #[serde(deserialize_with = "deserialize_duration")]
+    interval: Option<Duration>,
+    #[serde(deserialize_with = "deserialize_duration")]
+    break_length: Option<Duration>,
+    #[serde(deserialize_with = "deserialize_cycle_length")]
+    cycle_length: Option<u32>,
+    #[serde(deserialize_with = "deserialize_duration")]
+    long_break_length: Option<Duration>,
+    routine: Option<String>,
+    selection: Option<selection::Settings>,
+    goals: Option<stats::Goals>,
+    #[serde(deserialize_with = "deserialize_time")]
+    cycle_reset: Option<NaiveTime>,
+    #[serde(deserialize_with = "deserialize_duration")]
+    max_pause: Option<Duration>,
+    snooze: Option<Snooze>,
+    nag: Option<Nag>,
+    alerts: Option<Vec<notify::Kind>>,
+    #[serde(deserialize_with = "deserialize_volume")]
+    volume: Option<f32>,
+    sound: Option<PathBuf>,
+    sounds: Option<Sounds>,
+    /// In milliseconds.
+    #[serde(deserialize_with = "deserialize_tick_rate")]
+    tick_rate: Option<Duration>,
+    auto_mode: Option<bool>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            ConfigError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let text =
+            fs::read_to_string(path).map_err(|err| ConfigError::Io(path.to_path_buf(), err))?;
+        let raw: RawConfig =
+            toml::from_str(&text).map_err(|err| ConfigError::Parse(path.to_path_buf(), err))?;
+        let defaults = Config::default();
+
+        return Ok(Config {
+            interval: raw.interval.unwrap_or(defaults.interval),
+            break_length: raw.break_length.unwrap_or(defaults.break_length),
+            cycle_length: raw.cycle_length.or(defaults.cycle_length),
+            long_break_length: raw.long_break_length.unwrap_or(defaults.long_break_length),
+            routine: raw.routine.or(defaults.routine),
+            selection: raw.selection.unwrap_or(defaults.selection),
+            goals: raw.goals.unwrap_or(defaults.goals),
+            cycle_reset: raw.cycle_reset.unwrap_or(defaults.cycle_reset),
+            max_pause: raw.max_pause.or(defaults.max_pause),
+            snooze: raw.snooze.unwrap_or(defaults.snooze),
+            nag: raw.nag.or(defaults.nag),
+            alerts: raw.alerts.unwrap_or(defaults.alerts),
+            volume: raw.volume.unwrap_or(defaults.volume),
+            sound: raw.sound.or(defaults.sound),
+            sounds: raw.sounds.unwrap_or(defaults.sounds),
+            tick_rate: raw.tick_rate.unwrap_or(defaults.tick_rate),
+            auto_mode: raw.auto_mode.unwrap_or(defaults.auto_mode),
+        });
+    }
+
+    /// Loads the config from `path`, or from
+    /// `$XDG_CONFIG_HOME/stretchtime/config.toml` when no path is given.
+    ///
+    /// A missing default file is not an error; the path is still returned so
+    /// the file can be picked up once it is created.
+    pub fn find(path: Option<&Path>) -> Result<(Config, Option<PathBuf>), ConfigError> {
+        if let Some(path) = path {
+            return Ok((Config::load(path)?, Some(path.to_path_buf())));
+        }
+
+        let path = match paths::config_dir() {
+            Some(dir) => dir.join("config.toml"),
+            None => return Ok((Config::default(), None)),
+        };
+
+        if !path.exists() {
+            return Ok((Config::default(), Some(path)));
+        }
+
+        return Ok((Config::load(&path)?, Some(path)));
+    }
+
+    /// Applies command-line options, which take precedence over the file.
+    pub fn merge(&mut self, options: &cli::Options) {
+        if let Some(interval) = options.interval {
+            self.interval = interval;
+        }
+
+        if let Some(break_length) = options.break_length {
+            self.break_length = break_length;
+        }
+
+        if let Some(max_pause) = options.max_pause {
+            self.max_pause = Some(max_pause);
+        }
+
+        if let Some(volume) = options.volume {
+            self.volume = volume;
+        }
+
+        if let Some(sound) = &options.sound {
+            self.sound = Some(sound.clone());
+        }
+
+        if options.auto_mode {
+            self.auto_mode = true;
+        }
+    }
+
+    /// The file to play for `sound`, if any, or `None` for the built-in
+    /// chime.
+    pub fn sound_file(&self, sound: Sound) -> Option<&Path> {
+        let file = match sound {
+            Sound::BreakDue => &self.sounds.break_due,
+            Sound::BreakOver => &self.sounds.break_over,
+            Sound::Exercise => &self.sounds.exercise,
+            Sound::Goal => &self.sounds.goal,
+        };
+
+        return file.as_ref().or(self.sound.as_ref()).map(PathBuf::as_path);
+    }
+}
+
+/// Watches the config file so the running app can pick up edits.
+pub struct Reloader {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    options: cli::Options,
+}
+
+impl Reloader {
+    pub fn new(path: PathBuf, options: cli::Options) -> Reloader {
+        let modified = modified(&path);
+
+        Reloader {
+            path,
+            modified,
+            options,
+        }
+    }
+
+    /// Returns the reloaded config if the file changed since the last poll.
+    pub fn poll(&mut self) -> Option<Result<Config, ConfigError>> {
+        let modified = modified(&self.path);
+
+        if modified == self.modified {
+            return None;
+        }
+
+        self.modified = modified;
+
+        // keep the current settings while the file is missing
+        return modified.map(|_| {
+            Config::load(&self.path).map(|mut config| {
+                config.merge(&self.options);
+                config
+            })
+        });
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    return fs::metadata(path).and_then(|meta| meta.modified()).ok();
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    return duration::deserialize(deserializer).map(Some);
+}
+
+fn deserialize_volume<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f32>, D::Error> {
+    let volume = f32::deserialize(deserializer)?;
+
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(de::Error::custom(format!(
+            "volume {volume} is not between 0.0 and 1.0"
+        )));
+    }
+
+    return Ok(Some(volume));
+}
+
+fn deserialize_tick_rate<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    let milliseconds = u64::deserialize(deserializer)?;
+
+    if !(10..=1000).contains(&milliseconds) {
+        return Err(de::Error::custom(format!(
+            "tick rate {milliseconds} is not between 10 and 1000 milliseconds"
+        )));
+    }
+
+    return Ok(Some(Duration::from_millis(milliseconds)));
+}
+
+fn deserialize_cycle_length<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    let cycle_length = u32::deserialize(deserializer)?;
+
+    if cycle_length == 0 {
+        return Err(de::Error::custom("cycle length must be at least 1"));
+    }
+
+    return Ok(Some(cycle_length));
+}
+
+fn deserialize_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveTime>, D::Error> {
+    let time = String::deserialize(deserializer)?;
+
+    return NaiveTime::parse_from_str(&time, "%H:%M")
+        .map(Some)
+        .map_err(|_| {
+            de::Error::custom(format!("`{time}` is not a time of day such as \"04:00\""))
+        });
+}
@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant, SystemTime};
+
+/// A countdown anchored to the moment it was started rather than to the number
+/// of ticks it has received, so stalls in the UI loop never make it drift.
+pub struct Countdown {
+    duration: Duration,
+    /// Time consumed before the countdown was last paused.
+    consumed: Duration,
+    running_since: Option<(Instant, SystemTime)>,
+}
+
+impl Countdown {
+    pub fn new(duration: Duration) -> Countdown {
+        Countdown {
+            duration,
+            consumed: Duration::ZERO,
+            running_since: Some((Instant::now(), SystemTime::now())),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        return self.duration;
+    }
+
+    /// Time elapsed since the countdown was started, not counting pauses.
+    ///
+    /// The monotonic clock does not advance while the machine is suspended, but
+    /// the wall clock does, so the larger of the two is used. If the wall clock
+    /// is set backwards, the monotonic clock still wins.
+    pub fn elapsed(&self) -> Duration {
+        let (started, started_wall) = match self.running_since {
+            Some(since) => since,
+            None => return self.consumed,
+        };
+
+        let monotonic = started.elapsed();
+        let wall = SystemTime::now()
+            .duration_since(started_wall)
+            .unwrap_or_default();
+
+        return self.consumed + monotonic.max(wall);
+    }
+
+    pub fn remaining(&self) -> Duration {
+        return self.duration.saturating_sub(self.elapsed());
+    }
+
+    /// Remaining time in whole seconds, rounded up so that zero is only shown
+    /// once the deadline has actually passed.
+    pub fn remaining_seconds(&self) -> u64 {
+        let remaining = self.remaining();
+        let seconds = remaining.as_secs();
+
+        if remaining.subsec_nanos() > 0 {
+            return seconds + 1;
+        }
+
+        return seconds;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.remaining().is_zero();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        return self.running_since.is_none();
+    }
+
+    pub fn pause(&mut self) {
+        if !self.is_paused() {
+            self.consumed = self.elapsed();
+            self.running_since = None;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.is_paused() {
+            self.running_since = Some((Instant::now(), SystemTime::now()));
+        }
+    }
+}
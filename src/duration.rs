@@ -0,0 +1,292 @@
+use serde::de::{self, Deserializer, Visitor};
+
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind {
+    Empty,
+    Negative,
+    Zero,
+    Overflow,
+    InvalidNumber(String),
+    MissingUnit(String),
+    UnknownUnit(String),
+    DuplicateUnit(char),
+    OutOfRange(u64, &'static str),
+    TooManyFields,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    input: String,
+    kind: ErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.kind == ErrorKind::Empty {
+            return write!(f, "duration is empty");
+        }
+
+        write!(f, "invalid duration `{}`: ", self.input)?;
+
+        match &self.kind {
+            ErrorKind::Empty => unreachable!(),
+            ErrorKind::Negative => write!(f, "must not be negative"),
+            ErrorKind::Zero => write!(f, "must be greater than zero"),
+            ErrorKind::Overflow => write!(f, "too large"),
+            ErrorKind::InvalidNumber(number) => write!(f, "`{number}` is not a number"),
+            ErrorKind::MissingUnit(number) => {
+                write!(f, "`{number}` is missing a unit (h, m or s)")
+            }
+            ErrorKind::UnknownUnit(unit) => {
+                write!(f, "unknown unit `{unit}` (expected h, m or s)")
+            }
+            ErrorKind::DuplicateUnit(unit) => write!(f, "unit `{unit}` is given more than once"),
+            ErrorKind::OutOfRange(value, field) => {
+                write!(f, "{value} is out of range for {field} (0-59)")
+            }
+            ErrorKind::TooManyFields => write!(f, "expected HH:MM:SS or MM:SS"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a human-friendly, strictly positive duration.
+///
+/// Accepted forms are a bare number of seconds (`600`), unit components
+/// (`90s`, `25m`, `1h30m`, `1h 30m 15s`) and clock notation (`45:00`,
+/// `00:45:00`).
+pub fn parse(input: &str) -> std::result::Result<Duration, ParseError> {
+    let trimmed = input.trim();
+    let error = |kind| ParseError {
+        input: trimmed.to_string(),
+        kind,
+    };
+
+    if trimmed.is_empty() {
+        return Err(error(ErrorKind::Empty));
+    }
+
+    if trimmed.starts_with('-') {
+        return Err(error(ErrorKind::Negative));
+    }
+
+    let seconds = if trimmed.contains(':') {
+        parse_clock(trimmed)
+    } else if trimmed.chars().all(|c| c.is_ascii_digit()) {
+        parse_number(trimmed)
+    } else {
+        parse_units(trimmed)
+    }
+    .map_err(error)?;
+
+    if seconds == 0 {
+        return Err(error(ErrorKind::Zero));
+    }
+
+    return Ok(Duration::from_secs(seconds));
+}
+
+fn parse_number(number: &str) -> std::result::Result<u64, ErrorKind> {
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ErrorKind::InvalidNumber(number.to_string()));
+    }
+
+    return number.parse().map_err(|_| ErrorKind::Overflow);
+}
+
+fn parse_clock(input: &str) -> std::result::Result<u64, ErrorKind> {
+    let fields: Vec<&str> = input.split(':').collect();
+
+    let (hours, minutes, seconds) = match fields[..] {
+        [hours, minutes, seconds] => (parse_number(hours)?, minutes, seconds),
+        [minutes, seconds] => (0, minutes, seconds),
+        _ => return Err(ErrorKind::TooManyFields),
+    };
+
+    let minutes = parse_number(minutes)?;
+    let seconds = parse_number(seconds)?;
+
+    // the leading field may exceed its usual range, e.g. `90:00`
+    if fields.len() == 3 && minutes > 59 {
+        return Err(ErrorKind::OutOfRange(minutes, "minutes"));
+    }
+
+    if seconds > 59 {
+        return Err(ErrorKind::OutOfRange(seconds, "seconds"));
+    }
+
+    return hours
+        .checked_mul(3600)
+        .and_then(|total| total.checked_add(minutes.checked_mul(60)?))
+        .and_then(|total| total.checked_add(seconds))
+        .ok_or(ErrorKind::Overflow);
+}
+
+fn parse_units(input: &str) -> std::result::Result<u64, ErrorKind> {
+    let mut total: u64 = 0;
+    let mut seen = String::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number = &rest[..number_end];
+        rest = &rest[number_end..];
+
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_end];
+        rest = &rest[unit_end..];
+
+        if number.is_empty() {
+            return Err(ErrorKind::InvalidNumber(unit.to_string()));
+        }
+
+        let multiplier = match unit {
+            "h" => 3600,
+            "m" => 60,
+            "s" => 1,
+            "" => return Err(ErrorKind::MissingUnit(number.to_string())),
+            _ => return Err(ErrorKind::UnknownUnit(unit.to_string())),
+        };
+
+        if seen.contains(unit) {
+            return Err(ErrorKind::DuplicateUnit(unit.chars().next().unwrap()));
+        }
+        seen.push_str(unit);
+
+        total = parse_number(number)?
+            .checked_mul(multiplier)
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or(ErrorKind::Overflow)?;
+    }
+
+    return Ok(total);
+}
+
+pub fn format_hhmmss(total_seconds: u64) -> String {
+    let hours = total_seconds / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    let seconds = total_seconds % 3600 % 60;
+
+    return format!("{:02}:{:02}:{:02}", hours, minutes, seconds);
+}
+
+/// Formats a duration in the same unit notation `parse` accepts, e.g. `1h30m`.
+pub fn format(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+    let hours = total_seconds / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    let seconds = total_seconds % 60;
+
+    let mut formatted = String::new();
+
+    if hours > 0 {
+        formatted.push_str(&format!("{hours}h"));
+    }
+
+    if minutes > 0 {
+        formatted.push_str(&format!("{minutes}m"));
+    }
+
+    if seconds > 0 || formatted.is_empty() {
+        formatted.push_str(&format!("{seconds}s"));
+    }
+
+    return formatted;
+}
+
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a duration such as \"25m\" or a number of seconds")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> std::result::Result<Duration, E> {
+        return parse(&value.to_string()).map_err(E::custom);
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> std::result::Result<Duration, E> {
+        return parse(&value.to_string()).map_err(E::custom);
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<Duration, E> {
+        return parse(value).map_err(E::custom);
+    }
+}
+
+/// Deserializes a duration written as a string `parse` accepts, or as a
+/// number of seconds.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Duration, D::Error> {
+    return deserializer.deserialize_any(DurationVisitor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(input: &str) -> ErrorKind {
+        return parse(input).unwrap_err().kind;
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(parse("25m"), Ok(Duration::from_secs(25 * 60)));
+        assert_eq!(parse("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse("1h 30m 15s"), Ok(Duration::from_secs(90 * 60 + 15)));
+        assert_eq!(parse("90s"), Ok(Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn parses_seconds() {
+        assert_eq!(parse("600"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse(" 600 "), Ok(Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn parses_clock_notation() {
+        assert_eq!(parse("45:00"), Ok(Duration::from_secs(45 * 60)));
+        assert_eq!(parse("00:45:00"), Ok(Duration::from_secs(45 * 60)));
+        assert_eq!(parse("90:00"), Ok(Duration::from_secs(90 * 60)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert_eq!(kind(""), ErrorKind::Empty);
+        assert_eq!(kind("   "), ErrorKind::Empty);
+        assert_eq!(kind("0"), ErrorKind::Zero);
+        assert_eq!(kind("-5m"), ErrorKind::Negative);
+        assert_eq!(kind("1h1h"), ErrorKind::DuplicateUnit('h'));
+        assert_eq!(kind("1:60:00"), ErrorKind::OutOfRange(60, "minutes"));
+        assert_eq!(kind("1:2:3:4"), ErrorKind::TooManyFields);
+        assert_eq!(kind("5x"), ErrorKind::UnknownUnit("x".to_string()));
+        assert_eq!(kind("m"), ErrorKind::InvalidNumber("m".to_string()));
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert_eq!(kind("99999999999999999999"), ErrorKind::Overflow);
+        assert_eq!(kind("18446744073709551615h"), ErrorKind::Overflow);
+        assert_eq!(kind("18446744073709551615:00:00"), ErrorKind::Overflow);
+    }
+
+    #[test]
+    fn formats_what_it_parses() {
+        for input in ["25m", "1h30m", "1h30m15s", "1m30s"] {
+            assert_eq!(format(parse(input).unwrap()), input);
+        }
+    }
+}
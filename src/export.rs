@@ -0,0 +1,159 @@
+use chrono::{Duration, Utc};
+
+use std::io::{self, Write};
+
+use crate::history::{Event, Record};
+use crate::Phase;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    Ics,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "ics" | "ical" | "icalendar" => Some(Format::Ics),
+            _ => None,
+        }
+    }
+}
+
+pub fn write(records: &[Record], format: Format, out: &mut dyn Write) -> io::Result<()> {
+    match format {
+        Format::Csv => write_csv(records, out),
+        Format::Json => write_json(records, out),
+        Format::Ics => write_ics(records, out),
+    }
+}
+
+fn write_csv(records: &[Record], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "time,event,phase,long_break,snooze,planned,elapsed,exercises"
+    )?;
+
+    for record in records {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            record.time.to_rfc3339(),
+            record.event.name(),
+            record.phase.name().to_lowercase(),
+            record.long_break,
+            record.snooze,
+            record.planned,
+            record.elapsed,
+            csv_field(&record.exercises.join("; "))
+        )?;
+    }
+
+    return Ok(());
+}
+
+/// Quotes a field when it contains a separator, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+
+    return field.to_string();
+}
+
+fn write_json(records: &[Record], out: &mut dyn Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, records)?;
+
+    return writeln!(out);
+}
+
+/// Writes one event per break, spanning the time from its start until it
+/// completed or was cut short. Breaks put off or ended before any time was
+/// spent in them are left out.
+fn write_ics(records: &[Record], out: &mut dyn Write) -> io::Result<()> {
+    let stamp = ics_time(Utc::now());
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!(
+            "PRODID:-//stretchtime//stretchtime {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+    ];
+
+    let breaks = records
+        .iter()
+        .filter(|record| record.phase == Phase::Break && record.event != Event::Started)
+        .filter(|record| record.elapsed > 0);
+
+    for record in breaks {
+        let end = record.time.with_timezone(&Utc);
+        let start = end - Duration::seconds(record.elapsed as i64);
+
+        let mut description = format!("Break {}.", record.event.name());
+
+        if !record.exercises.is_empty() {
+            description.push_str(&format!(" Exercises: {}.", record.exercises.join(", ")));
+        }
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            // down to the nanosecond, so that breaks recorded within the
+            // same second are not merged
+            format!(
+                "UID:{}.{:09}@stretchtime",
+                record.time.timestamp(),
+                record.time.timestamp_subsec_nanos()
+            ),
+            format!("DTSTAMP:{stamp}"),
+            format!("DTSTART:{}", ics_time(start)),
+            format!("DTEND:{}", ics_time(end)),
+            format!("SUMMARY:{}", ics_text(record.phase_name())),
+            format!("DESCRIPTION:{}", ics_text(&description)),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    for line in lines {
+        write!(out, "{}\r\n", ics_fold(&line))?;
+    }
+
+    return Ok(());
+}
+
+fn ics_time(time: chrono::DateTime<Utc>) -> String {
+    return time.format("%Y%m%dT%H%M%SZ").to_string();
+}
+
+fn ics_text(text: &str) -> String {
+    return text
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n");
+}
+
+/// Folds a content line so that no physical line exceeds 75 bytes, as
+/// RFC 5545 requires, without splitting a character.
+fn ics_fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    return folded;
+}
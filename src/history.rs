@@ -0,0 +1,163 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use crate::{paths, Phase};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    /// A phase began.
+    Started,
+    /// A phase ran until its countdown reached zero.
+    Completed,
+    /// A phase was ended early to move on to the next one.
+    Skipped,
+    /// The work interval was restarted.
+    Reset,
+    /// A break that was due was put off.
+    Snoozed,
+    /// The app was closed in the middle of a phase.
+    Quit,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Started => "started",
+            Event::Completed => "completed",
+            Event::Skipped => "skipped",
+            Event::Reset => "reset",
+            Event::Snoozed => "snoozed",
+            Event::Quit => "quit",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub time: DateTime<Local>,
+    pub event: Event,
+    pub phase: Phase,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub long_break: bool,
+    /// The phase was a snooze, putting off a break that was already due.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub snooze: bool,
+    /// Planned length of the phase, in seconds.
+    pub planned: u64,
+    /// Time spent in the phase when the event happened, in seconds.
+    pub elapsed: u64,
+    /// Exercises performed during a break.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exercises: Vec<String>,
+}
+
+impl Record {
+    pub fn phase_name(&self) -> &'static str {
+        if self.long_break {
+            return "Long break";
+        }
+
+        if self.snooze {
+            return "Snooze";
+        }
+
+        return self.phase.name();
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    return !*value;
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    NoDataDir,
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, usize, serde_json::Error),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HistoryError::NoDataDir => write!(f, "no data directory; set $XDG_DATA_HOME or $HOME"),
+            HistoryError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            HistoryError::Parse(path, line, err) => {
+                write!(f, "{}:{line}: {err}", path.display())
+            }
+        }
+    }
+}
+
+/// Append-only log of cycle events, one JSON object per line, in
+/// `$XDG_DATA_HOME/stretchtime/history.jsonl`.
+pub struct History {
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// A log kept in the given file instead.
+    #[cfg(test)]
+    pub fn at(path: PathBuf) -> History {
+        History { path: Some(path) }
+    }
+
+    pub fn open() -> History {
+        History {
+            path: paths::data_dir().map(|dir| dir.join("history.jsonl")),
+        }
+    }
+
+    pub fn append(&self, record: &Record) -> Result<(), HistoryError> {
+        let path = self.path.as_ref().ok_or(HistoryError::NoDataDir)?;
+        let io_error = |err| HistoryError::Io(path.clone(), err);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+
+        let mut line = serde_json::to_string(record)
+            .map_err(|err| io_error(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+
+        return file.write_all(line.as_bytes()).map_err(io_error);
+    }
+
+    /// Reads every record, oldest first. A missing file is an empty history.
+    pub fn load(&self) -> Result<Vec<Record>, HistoryError> {
+        let path = self.path.as_ref().ok_or(HistoryError::NoDataDir)?;
+
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(HistoryError::Io(path.clone(), err)),
+        };
+
+        let mut records = Vec::new();
+
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| HistoryError::Io(path.clone(), err))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record = serde_json::from_str(&line)
+                .map_err(|err| HistoryError::Parse(path.clone(), index + 1, err))?;
+            records.push(record);
+        }
+
+        return Ok(records);
+    }
+}
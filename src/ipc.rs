@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs::{self, DirBuilder};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::{duration, paths};
+
+/// A request to the running instance, sent as one line of text, e.g.
+/// `set-interval 25m`. Each request gets a one-line reply: `ok`, `ok`
+/// followed by a JSON object for `status`, or `error` and a message.
+#[derive(Clone, Copy)]
+pub enum Command {
+    Status,
+    Pause,
+    Resume,
+    Reset,
+    Snooze,
+    Skip,
+    SetInterval(Duration),
+    ToggleAuto,
+    /// Stops a repeating alert.
+    Acknowledge,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let (name, argument) = match line.trim().split_once(' ') {
+            Some((name, argument)) => (name, Some(argument.trim())),
+            None => (line.trim(), None),
+        };
+
+        let command = match name {
+            "status" => Command::Status,
+            "pause" => Command::Pause,
+            "resume" => Command::Resume,
+            "reset" => Command::Reset,
+            "snooze" => Command::Snooze,
+            "skip" => Command::Skip,
+            "toggle-auto" => Command::ToggleAuto,
+            "acknowledge" => Command::Acknowledge,
+            "set-interval" => {
+                let argument = argument.ok_or("`set-interval` requires a duration")?;
+                let interval = duration::parse(argument).map_err(|err| err.to_string())?;
+
+                return Ok(Command::SetInterval(interval));
+            }
+            _ => return Err(format!("unknown command `{name}`")),
+        };
+
+        if argument.is_some() {
+            return Err(format!("`{name}` takes no argument"));
+        }
+
+        return Ok(command);
+    }
+
+    fn line(self) -> String {
+        match self {
+            Command::Status => "status".to_string(),
+            Command::Pause => "pause".to_string(),
+            Command::Resume => "resume".to_string(),
+            Command::Reset => "reset".to_string(),
+            Command::Snooze => "snooze".to_string(),
+            Command::Skip => "skip".to_string(),
+            Command::SetInterval(interval) => {
+                format!("set-interval {}", duration::format(interval))
+            }
+            Command::ToggleAuto => "toggle-auto".to_string(),
+            Command::Acknowledge => "acknowledge".to_string(),
+        }
+    }
+}
+
+/// State of the running timer, as reported by `status`.
+#[derive(Serialize, Deserialize)]
+pub struct Status {
+    pub phase: String,
+    /// Name of the phase as shown in the interface, e.g. "Long break".
+    pub title: String,
+    /// Length of the phase, in seconds.
+    pub duration: u64,
+    /// Remaining time, in seconds.
+    pub remaining: u64,
+    pub paused: bool,
+    pub auto_mode: bool,
+    /// Position in the cycle, if cycles are enabled.
+    pub cycle: Option<String>,
+    /// Current exercise during a break.
+    pub exercise: Option<String>,
+}
+
+pub type Reply = Result<Option<Status>, String>;
+
+#[derive(Debug)]
+pub enum IpcError {
+    NoRuntimeDir,
+    AlreadyRunning(PathBuf),
+    NotRunning(PathBuf),
+    Io(PathBuf, io::Error),
+    /// The instance replied with something that is not part of the protocol.
+    Protocol(String),
+    /// The instance refused the request.
+    Refused(String),
+}
+
+impl fmt::Display for IpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpcError::NoRuntimeDir => write!(f, "no runtime directory; set $XDG_RUNTIME_DIR"),
+            IpcError::AlreadyRunning(path) => {
+                write!(f, "another instance is listening on {}", path.display())
+            }
+            IpcError::NotRunning(path) => {
+                write!(f, "no running instance found at {}", path.display())
+            }
+            IpcError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            IpcError::Protocol(reply) => write!(f, "unexpected reply `{reply}`"),
+            IpcError::Refused(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+fn socket_path() -> Result<PathBuf, IpcError> {
+    return paths::runtime_dir()
+        .map(|dir| dir.join("control.sock"))
+        .ok_or(IpcError::NoRuntimeDir);
+}
+
+/// A request waiting for the app to carry it out.
+pub struct Request {
+    pub command: Command,
+    reply: Sender<Reply>,
+}
+
+impl Request {
+    pub fn respond(self, reply: Reply) {
+        let _ = self.reply.send(reply);
+    }
+}
+
+/// Listens on `$XDG_RUNTIME_DIR/stretchtime/control.sock`. Connections are
+/// read on their own threads and requests are handed to the app through
+/// `poll`, so they are carried out between ticks.
+pub struct Server {
+    path: PathBuf,
+    receiver: Receiver<Request>,
+}
+
+impl Server {
+    pub fn bind() -> Result<Server, IpcError> {
+        let path = socket_path()?;
+        let io_error = |err| IpcError::Io(path.clone(), err);
+
+        if UnixStream::connect(&path).is_ok() {
+            return Err(IpcError::AlreadyRunning(path));
+        }
+
+        // left behind by an instance that did not exit cleanly
+        let _ = fs::remove_file(&path);
+
+        if let Some(dir) = path.parent() {
+            DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(dir)
+                .map_err(io_error)?;
+        }
+
+        let listener = UnixListener::bind(&path).map_err(io_error)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(io_error)?;
+
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || serve(stream, sender));
+            }
+        });
+
+        return Ok(Server { path, receiver });
+    }
+
+    pub fn poll(&self) -> Option<Request> {
+        return self.receiver.try_recv().ok();
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn serve(stream: UnixStream, sender: Sender<Request>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match Command::parse(&line) {
+            Ok(command) => {
+                let (reply, receiver) = mpsc::channel();
+
+                if sender.send(Request { command, reply }).is_err() {
+                    return;
+                }
+
+                match receiver.recv() {
+                    Ok(reply) => reply,
+                    Err(_) => return,
+                }
+            }
+            Err(message) => Err(message),
+        };
+
+        let line = match reply {
+            Ok(None) => "ok".to_string(),
+            Ok(Some(status)) => match serde_json::to_string(&status) {
+                Ok(json) => format!("ok {json}"),
+                Err(err) => format!("error {err}"),
+            },
+            Err(message) => format!("error {}", message.replace('\n', " ")),
+        };
+
+        if writeln!(writer, "{line}").is_err() {
+            return;
+        }
+    }
+}
+
+/// A connection to the running instance.
+pub struct Client {
+    path: PathBuf,
+    stream: BufReader<UnixStream>,
+}
+
+impl Client {
+    pub fn connect() -> Result<Client, IpcError> {
+        let path = socket_path()?;
+
+        let stream = match UnixStream::connect(&path) {
+            Ok(stream) => stream,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+                ) =>
+            {
+                return Err(IpcError::NotRunning(path));
+            }
+            Err(err) => return Err(IpcError::Io(path, err)),
+        };
+
+        return Ok(Client {
+            path,
+            stream: BufReader::new(stream),
+        });
+    }
+
+    pub fn send(&mut self, command: Command) -> Result<Option<Status>, IpcError> {
+        let io_error = |err| IpcError::Io(self.path.clone(), err);
+
+        writeln!(self.stream.get_mut(), "{}", command.line()).map_err(io_error)?;
+
+        let mut line = String::new();
+        let read = self.stream.read_line(&mut line).map_err(io_error)?;
+
+        if read == 0 {
+            return Err(IpcError::NotRunning(self.path.clone()));
+        }
+
+        let line = line.trim_end();
+        let (status, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        return match status {
+            "ok" if rest.is_empty() => Ok(None),
+            "ok" => serde_json::from_str(rest)
+                .map(Some)
+                .map_err(|_| IpcError::Protocol(line.to_string())),
+            "error" => Err(IpcError::Refused(rest.to_string())),
+            _ => Err(IpcError::Protocol(line.to_string())),
+        };
+    }
+}
@@ -0,0 +1,276 @@
+use serde::Deserialize;
+use toml::Spanned;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::duration;
+use crate::paths;
+use crate::routine::{Difficulty, Exercise, Routine, Sides};
+
+const BUILTIN: &str = include_str!("../resources/exercises.toml");
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    #[serde(default)]
+    exercise: Vec<RawExercise>,
+    #[serde(default)]
+    routine: Vec<RawRoutine>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawExercise {
+    name: Spanned<String>,
+    #[serde(default)]
+    description: String,
+    #[serde(deserialize_with = "duration::deserialize")]
+    duration: Duration,
+    repetitions: Option<u32>,
+    #[serde(default)]
+    sides: Sides,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    difficulty: Difficulty,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRoutine {
+    name: Spanned<String>,
+    exercises: Vec<Spanned<String>>,
+}
+
+/// A routine as defined in a file, resolved against the library when used so
+/// that exercises replaced by later files are picked up.
+struct RoutineDefinition {
+    name: String,
+    exercises: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum LibraryError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LibraryError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            LibraryError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+            LibraryError::Invalid {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+        }
+    }
+}
+
+pub struct Library {
+    exercises: Vec<Exercise>,
+    routines: Vec<RoutineDefinition>,
+}
+
+impl Library {
+    /// The library compiled into the binary.
+    pub fn builtin() -> Library {
+        let mut library = Library {
+            exercises: Vec::new(),
+            routines: Vec::new(),
+        };
+
+        library
+            .add(Path::new("<built-in>"), BUILTIN)
+            .expect("the built-in exercise library is valid");
+
+        return library;
+    }
+
+    /// A library with only the exercises and routines in `text`.
+    #[cfg(test)]
+    pub fn parse(text: &str) -> Library {
+        let mut library = Library {
+            exercises: Vec::new(),
+            routines: Vec::new(),
+        };
+
+        library
+            .add(Path::new("<test>"), text)
+            .expect("the test library is valid");
+
+        return library;
+    }
+
+    /// Loads the built-in library, then every `*.toml` file in
+    /// `$XDG_DATA_HOME/stretchtime/exercises` in name order. Exercises and
+    /// routines replace earlier ones with the same name.
+    pub fn load() -> Result<Library, LibraryError> {
+        let mut library = Library::builtin();
+
+        let dir = match paths::data_dir() {
+            Some(dir) => dir.join("exercises"),
+            None => return Ok(library),
+        };
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(library),
+            Err(err) => return Err(LibraryError::Io(dir, err)),
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "toml")
+            })
+            .collect();
+        files.sort();
+
+        for path in files {
+            let text =
+                fs::read_to_string(&path).map_err(|err| LibraryError::Io(path.clone(), err))?;
+            library.add(&path, &text)?;
+        }
+
+        return Ok(library);
+    }
+
+    pub fn exercises(&self) -> &[Exercise] {
+        return &self.exercises;
+    }
+
+    pub fn exercise(&self, name: &str) -> Option<&Exercise> {
+        return self.exercises.iter().find(|exercise| exercise.name == name);
+    }
+
+    pub fn routine(&self, name: &str) -> Option<Routine> {
+        let definition = self.routines.iter().find(|routine| routine.name == name)?;
+
+        return Some(Routine {
+            name: definition.name.clone(),
+            exercises: definition
+                .exercises
+                .iter()
+                .filter_map(|name| self.exercise(name).cloned())
+                .collect(),
+        });
+    }
+
+    fn add(&mut self, path: &Path, text: &str) -> Result<(), LibraryError> {
+        let file: File =
+            toml::from_str(text).map_err(|err| LibraryError::Parse(path.to_path_buf(), err))?;
+
+        let invalid = |offset: usize, message: String| LibraryError::Invalid {
+            path: path.to_path_buf(),
+            line: line_of(text, offset),
+            message,
+        };
+
+        let mut defined: Vec<String> = Vec::new();
+
+        for raw in file.exercise {
+            let name = raw.name.get_ref().trim().to_string();
+
+            if name.is_empty() {
+                return Err(invalid(
+                    raw.name.start(),
+                    "exercise name is empty".to_string(),
+                ));
+            }
+
+            if defined.contains(&name) {
+                return Err(invalid(
+                    raw.name.start(),
+                    format!("exercise `{name}` is defined more than once"),
+                ));
+            }
+            defined.push(name.clone());
+
+            let exercise = Exercise {
+                name,
+                description: raw.description,
+                duration: raw.duration,
+                repetitions: raw.repetitions,
+                sides: raw.sides,
+                tags: raw.tags,
+                difficulty: raw.difficulty,
+            };
+
+            match self.exercises.iter_mut().find(|e| e.name == exercise.name) {
+                Some(existing) => *existing = exercise,
+                None => self.exercises.push(exercise),
+            }
+        }
+
+        defined.clear();
+
+        for raw in file.routine {
+            let name = raw.name.get_ref().trim().to_string();
+
+            if name.is_empty() {
+                return Err(invalid(
+                    raw.name.start(),
+                    "routine name is empty".to_string(),
+                ));
+            }
+
+            if defined.contains(&name) {
+                return Err(invalid(
+                    raw.name.start(),
+                    format!("routine `{name}` is defined more than once"),
+                ));
+            }
+            defined.push(name.clone());
+
+            if raw.exercises.is_empty() {
+                return Err(invalid(
+                    raw.name.start(),
+                    format!("routine `{name}` has no exercises"),
+                ));
+            }
+
+            for exercise in &raw.exercises {
+                if self.exercise(exercise.get_ref()).is_none() {
+                    return Err(invalid(
+                        exercise.start(),
+                        format!(
+                            "routine `{name}` uses unknown exercise `{}`",
+                            exercise.get_ref()
+                        ),
+                    ));
+                }
+            }
+
+            let routine = RoutineDefinition {
+                name,
+                exercises: raw.exercises.into_iter().map(Spanned::into_inner).collect(),
+            };
+
+            match self.routines.iter_mut().find(|r| r.name == routine.name) {
+                Some(existing) => *existing = routine,
+                None => self.routines.push(routine),
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// One-based line number of a byte offset into `text`.
+fn line_of(text: &str, offset: usize) -> usize {
+    return text.get(..offset).unwrap_or(text).matches('\n').count() + 1;
+}
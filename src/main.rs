@@ -1,115 +1,1251 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use soloud::{AudioExt, LoadExt};
+use serde::{Deserialize, Serialize};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Direction, Layout},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{BarChart, Block, Borders, Paragraph},
     Frame, Terminal,
 };
 
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
 use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use audio::{Audio, Sound, SoundError};
+use cli::Command;
+use config::{Config, ConfigError, Reloader};
+use countdown::Countdown;
+use history::{Event, History, HistoryError, Record};
+use ipc::{IpcError, Server};
+use library::{Library, LibraryError};
+use notify::{Action, Alert, Notifier};
+use routine::{Outcome, Routine, Session};
+use selection::Selector;
+use state::State;
+use stats::Stats;
+
+mod audio;
+mod cli;
+mod config;
+mod countdown;
+mod duration;
+mod export;
+mod history;
+mod ipc;
+mod library;
+mod notify;
+mod paths;
+mod routine;
+mod selection;
+mod state;
+mod stats;
+mod status;
 
 type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug)]
-enum Error {
-    Command,
-    RawMode,
-    Terminal,
-}
+/// How long after a break starts it can still be snoozed.
+const SNOOZE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+enum Error {
+    Config(ConfigError),
+    Library(LibraryError),
+    History(HistoryError),
+    /// Writing the export failed, to the given file or to standard output.
+    Export(Option<PathBuf>, std::io::Error),
+    UnknownRoutine(String),
+    Command,
+    RawMode,
+    Terminal,
+    Signal(std::io::Error),
+    Ipc(IpcError),
+    /// Reading input or drawing failed while the timer was running.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Config(err) => write!(f, "{err}"),
+            Error::Library(err) => write!(f, "{err}"),
+            Error::History(err) => write!(f, "{err}"),
+            Error::Export(Some(path), err) => write!(f, "{}: {err}", path.display()),
+            Error::Export(None, err) => write!(f, "failed to write the export: {err}"),
+            Error::UnknownRoutine(name) => write!(f, "unknown routine `{name}`"),
+            Error::Command => write!(f, "failed to set up the terminal"),
+            Error::RawMode => write!(f, "failed to toggle terminal raw mode"),
+            Error::Terminal => write!(f, "failed to draw to the terminal"),
+            Error::Signal(err) => write!(f, "failed to set up signal handling: {err}"),
+            Error::Ipc(err) => write!(f, "{err}"),
+            Error::Io(err) => write!(f, "failed to run the timer: {err}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Phase {
+    Work,
+    Break,
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Work => "Work",
+            Phase::Break => "Break",
+        }
+    }
+
+    fn next(self) -> Phase {
+        match self {
+            Phase::Work => Phase::Break,
+            Phase::Break => Phase::Work,
+        }
+    }
+}
+
+struct App {
+    phase: Phase,
+    countdown: Countdown,
+    countdown_config: Duration,
+    break_config: Duration,
+    completed_intervals: u32,
+    cycle_day: NaiveDate,
+    reset: bool,
+    auto_mode: bool,
+    sound_played: bool,
+    /// Times the break was snoozed during the current work interval.
+    snoozes: u32,
+    /// The alert being repeated until acknowledged with its sound, when to
+    /// repeat it next, and how often it was repeated.
+    nagging: Option<(Alert, Sound, Countdown, u32)>,
+    volume: f32,
+    muted: bool,
+    max_pause: Option<Duration>,
+    pause_timeout: Option<Countdown>,
+    session: Option<Session>,
+    input: Option<String>,
+    message: Option<String>,
+    /// Shown instead of the timer while set.
+    stats: Option<Stats>,
+    /// Whether today's goal was met, so that it is only announced once.
+    goal_met: bool,
+    config: Config,
+    library: Library,
+    selector: Selector,
+    history: History,
+    notifiers: Vec<Box<dyn Notifier>>,
+    /// Running without the terminal interface.
+    headless: bool,
+    /// Id of the last alert sent.
+    alert: u64,
+    audio: Audio,
+}
+
+impl App {
+    fn new(
+        config: Config,
+        library: Library,
+        audio: Audio,
+        history: History,
+        headless: bool,
+    ) -> App {
+        let mut app = App {
+            phase: Phase::Work,
+            countdown: Countdown::new(config.interval),
+            countdown_config: config.interval,
+            break_config: config.break_length,
+            completed_intervals: 0,
+            cycle_day: cycle_day(config.cycle_reset),
+            reset: false,
+            auto_mode: config.auto_mode,
+            sound_played: false,
+            snoozes: 0,
+            nagging: None,
+            volume: config.volume,
+            muted: false,
+            max_pause: config.max_pause,
+            pause_timeout: None,
+            session: None,
+            input: None,
+            message: None,
+            stats: None,
+            goal_met: false,
+            selector: Selector::new(config.selection.clone()),
+            config,
+            library,
+            history,
+            notifiers: Vec::new(),
+            headless,
+            alert: 0,
+            audio,
+        };
+
+        app.load_notifiers();
+
+        app.goal_met = app.compute_stats().is_ok_and(|stats| stats.goal_met());
+
+        return app;
+    }
+
+    /// Applies the settings that changed since the config was last loaded,
+    /// leaving anything changed at runtime alone.
+    fn apply_config(&mut self, config: Config) {
+        let previous = std::mem::replace(&mut self.config, config);
+        self.message = Some("Config reloaded".to_string());
+
+        if self.config.interval != previous.interval {
+            self.set_interval(self.config.interval);
+        }
+
+        if self.config.break_length != previous.break_length {
+            self.set_break_length(self.config.break_length);
+        }
+
+        if let Some(name) = &self.config.routine {
+            if self.library.routine(name).is_none() {
+                self.message = Some(format!("Config reloaded, but routine `{name}` is unknown"));
+            }
+        }
+
+        if self.config.selection != previous.selection {
+            self.selector = Selector::new(self.config.selection.clone());
+        }
+
+        if self.config.goals != previous.goals {
+            self.goal_met = self.compute_stats().is_ok_and(|stats| stats.goal_met());
+        }
+
+        if self.config.max_pause != previous.max_pause {
+            self.max_pause = self.config.max_pause;
+        }
+
+        if self.config.volume != previous.volume {
+            self.volume = self.config.volume;
+        }
+
+        if self.config.auto_mode != previous.auto_mode {
+            self.auto_mode = self.config.auto_mode;
+        }
+
+        if self.config.alerts != previous.alerts {
+            self.load_notifiers();
+        }
+
+        if self.config.sound != previous.sound || self.config.sounds != previous.sounds {
+            let (audio, errors) = Audio::new(&self.config);
+            self.audio = audio;
+
+            if let Some(errors) = sound_errors(&errors) {
+                self.message = Some(format!("Config reloaded, but {errors}"));
+            }
+        }
+    }
+
+    fn on_tick(&mut self) {
+        let day = cycle_day(self.config.cycle_reset);
+        if day != self.cycle_day {
+            self.cycle_day = day;
+            self.completed_intervals = 0;
+            self.goal_met = false;
+        }
+
+        if self
+            .pause_timeout
+            .as_ref()
+            .is_some_and(Countdown::is_finished)
+        {
+            self.resume();
+        }
+
+        self.poll_notifiers();
+        self.show_status();
+
+        if self.session.as_mut().is_some_and(Session::on_tick) {
+            self.play_sound(Sound::Exercise);
+        }
+
+        let finished = self.countdown.is_finished();
+
+        self.nag();
+
+        if finished && !self.sound_played {
+            let sound = match self.phase {
+                Phase::Work => Sound::BreakDue,
+                Phase::Break => Sound::BreakOver,
+            };
+            let alert = self.phase_alert();
+            self.send_alert(&alert, sound);
+            self.record(Event::Completed);
+            self.sound_played = true;
+
+            if let Some(nag) = self.config.nag.as_ref().filter(|nag| nag.max > 0) {
+                self.nagging = Some((alert, sound, Countdown::new(nag.interval), 0));
+            }
+        }
+
+        // the new countdown starts from now, so a deadline that passed while
+        // the machine was suspended fires once instead of catching up
+        if finished && self.auto_mode {
+            self.next_phase();
+        }
+
+        if self.reset {
+            self.end_phase(Event::Reset);
+            self.start_phase(Phase::Work);
+            self.reset = false;
+        }
+    }
+
+    fn start_phase(&mut self, phase: Phase) {
+        let duration = match phase {
+            Phase::Work => self.countdown_config,
+            Phase::Break if self.long_break_due() => self.config.long_break_length,
+            Phase::Break => self.break_config,
+        };
+
+        if phase == Phase::Work {
+            self.snoozes = 0;
+        }
+
+        self.phase = phase;
+        self.countdown = Countdown::new(duration);
+        self.sound_played = false;
+        self.pause_timeout = None;
+        // the alert of the last phase no longer applies
+        self.nagging = None;
+        self.session = match phase {
+            Phase::Work => None,
+            Phase::Break => Some(Session::new(self.break_routine(duration))),
+        };
+
+        self.record(Event::Started);
+    }
+
+    /// Records how the current phase ended, unless it already ran to
+    /// completion and was recorded as such.
+    fn end_phase(&mut self, event: Event) {
+        if !self.countdown.is_finished() {
+            self.record(event);
+        } else if !self.sound_played {
+            self.record(Event::Completed);
+        }
+    }
+
+    fn record(&mut self, event: Event) {
+        let planned = self.countdown.duration();
+        let record = Record {
+            time: Local::now(),
+            event,
+            phase: self.phase,
+            long_break: self.phase == Phase::Break && self.long_break_due(),
+            snooze: self.phase == Phase::Work && self.snoozes > 0,
+            planned: planned.as_secs(),
+            elapsed: self.countdown.elapsed().min(planned).as_secs(),
+            exercises: self
+                .session
+                .as_ref()
+                .map(Session::performed)
+                .unwrap_or_default(),
+        };
+
+        if let Err(err) = self.history.append(&record) {
+            self.message = Some(format!("History not saved: {err}"));
+        }
+
+        if self.stats.is_some() {
+            self.load_stats();
+        }
+
+        if self.phase == Phase::Break && event != Event::Started {
+            self.check_goal();
+        }
+    }
+
+    fn compute_stats(&self) -> std::result::Result<Stats, HistoryError> {
+        let records = self.history.load()?;
+        let today = cycle_day(self.config.cycle_reset);
+
+        return Ok(Stats::new(
+            &records,
+            today,
+            self.config.cycle_reset,
+            self.config.goals.clone(),
+        ));
+    }
+
+    /// Announces the daily goal the first time it is met.
+    fn check_goal(&mut self) {
+        if self.goal_met || !self.config.goals.is_set() {
+            return;
+        }
+
+        let stats = match self.compute_stats() {
+            Ok(stats) => stats,
+            Err(_) => return,
+        };
+
+        if stats.goal_met() {
+            let streak = stats.streak();
+            let plural = if streak == 1 { "" } else { "s" };
+
+            self.goal_met = true;
+            self.message = Some(format!("Daily goal met! Streak: {streak} day{plural}"));
+            self.play_sound(Sound::Goal);
+        }
+    }
+
+    fn toggle_stats(&mut self) {
+        if self.stats.take().is_none() {
+            self.load_stats();
+        }
+    }
+
+    fn load_stats(&mut self) {
+        match self.compute_stats() {
+            Ok(stats) => self.stats = Some(stats),
+            Err(err) => {
+                self.stats = None;
+                self.message = Some(format!("History not loaded: {err}"));
+            }
+        }
+    }
+
+    fn quit(&mut self) {
+        self.end_phase(Event::Quit);
+    }
+
+    /// The configured routine, or a selection of exercises that fits in the
+    /// break.
+    fn break_routine(&mut self, budget: Duration) -> Routine {
+        let configured = self.config.routine.as_deref();
+
+        if let Some(routine) = configured.and_then(|name| self.library.routine(name)) {
+            return routine;
+        }
+
+        return self.selector.select(&self.library, budget);
+    }
+
+    fn next_exercise(&mut self) {
+        if let Some(session) = self.session.as_mut() {
+            session.next();
+        }
+    }
+
+    fn previous_exercise(&mut self) {
+        if let Some(session) = self.session.as_mut() {
+            session.previous();
+        }
+    }
+
+    fn skip_exercise(&mut self) {
+        if let Some(session) = self.session.as_mut() {
+            session.skip();
+        }
+    }
+
+    fn get_exercise_strings(&self) -> Option<(String, String, String)> {
+        let session = self.session.as_ref()?;
+
+        let exercise = match session.current() {
+            Some(exercise) => exercise,
+            None => {
+                return Some((
+                    format!("{} complete", session.name()),
+                    format!(
+                        "{} done, {} skipped. Relax until the break ends.",
+                        session.count(Outcome::Done),
+                        session.count(Outcome::Skipped)
+                    ),
+                    String::new(),
+                ))
+            }
+        };
+
+        let (step, steps) = session.position();
+
+        return Some((
+            format!(
+                "{step}/{steps} {} {}",
+                exercise.name,
+                duration::format_hhmmss(session.countdown().remaining_seconds())
+            ),
+            exercise.description.clone(),
+            exercise.details(),
+        ));
+    }
+
+    /// Ends the current phase early, or starts the next one once it is over.
+    fn next_phase(&mut self) {
+        self.end_phase(Event::Skipped);
+
+        if self.phase == Phase::Work {
+            self.completed_intervals += 1;
+        }
+
+        self.start_phase(self.phase.next());
+    }
+
+    /// Whether the last completed work interval closed a cycle.
+    fn long_break_due(&self) -> bool {
+        return match self.config.cycle_length {
+            Some(length) => {
+                self.completed_intervals > 0 && self.completed_intervals.is_multiple_of(length)
+            }
+            None => false,
+        };
+    }
+
+    fn get_title_string(&self) -> &'static str {
+        if self.phase == Phase::Break && self.long_break_due() {
+            return "Long break";
+        }
+
+        return self.phase.name();
+    }
+
+    fn get_cycle_string(&self) -> Option<String> {
+        let length = self.config.cycle_length?;
+        let position = match self.phase {
+            Phase::Work => self.completed_intervals % length + 1,
+            Phase::Break => (self.completed_intervals + length - 1) % length + 1,
+        };
+
+        return Some(format!("Cycle {position}/{length}"));
+    }
+
+    fn get_phase_string(&self) -> Option<String> {
+        if !self.countdown.is_finished() || self.auto_mode {
+            return None;
+        }
+
+        return Some(
+            match self.phase {
+                Phase::Work => "Time to stretch! Press Enter to start the break",
+                Phase::Break => "Break over. Press Enter to get back to work",
+            }
+            .to_string(),
+        );
+    }
+
+    fn get_hhmmss(&self) -> String {
+        let remaining = self.countdown.remaining_seconds();
+
+        return format!("{} ({})", duration::format_hhmmss(remaining), remaining);
+    }
+
+    fn get_audio_string(&self) -> Option<String> {
+        if !self.audio.is_silent() {
+            return None;
+        }
+
+        return Some("No audio device found, sounds are off".to_string());
+    }
+
+    fn get_paused_string(&self) -> Option<String> {
+        if !self.countdown.is_paused() {
+            return None;
+        }
+
+        return match &self.pause_timeout {
+            Some(timeout) => Some(format!(
+                "PAUSED (resumes in {})",
+                duration::format_hhmmss(timeout.remaining_seconds())
+            )),
+            None => Some("PAUSED".to_string()),
+        };
+    }
+
+    fn get_automode_string(&self) -> String {
+        return format!("Auto mode: {}", self.auto_mode);
+    }
+
+    fn reset(&mut self) {
+        self.reset = true
+    }
+
+    fn get_input_string(&self) -> Option<String> {
+        return self
+            .input
+            .as_ref()
+            .map(|input| format!("New interval: {input}_"));
+    }
+
+    fn set_interval(&mut self, interval: Duration) {
+        self.countdown_config = interval;
+
+        if self.phase == Phase::Work {
+            self.reset();
+        }
+    }
+
+    fn set_break_length(&mut self, break_length: Duration) {
+        self.break_config = break_length;
+
+        if self.phase == Phase::Break {
+            self.end_phase(Event::Reset);
+            self.start_phase(Phase::Break);
+        }
+    }
+
+    fn edit_interval(&mut self) {
+        self.input = Some(String::new());
+        self.message = None;
+    }
+
+    fn on_input_key(&mut self, code: KeyCode) {
+        let input = match self.input.as_mut() {
+            Some(input) => input,
+            None => return,
+        };
+
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.input = None,
+            KeyCode::Enter => {
+                match duration::parse(input) {
+                    Ok(interval) => {
+                        self.set_interval(interval);
+                        self.message =
+                            Some(format!("Interval set to {}", duration::format(interval)));
+                    }
+                    Err(err) => self.message = Some(err.to_string()),
+                }
+
+                self.input = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn pause(&mut self) {
+        if self.countdown.is_paused() {
+            return;
+        }
+
+        self.countdown.pause();
+        self.pause_timeout = self.max_pause.map(Countdown::new);
+
+        if let Some(session) = self.session.as_mut() {
+            session.pause();
+        }
+    }
+
+    fn resume(&mut self) {
+        self.countdown.resume();
+        self.pause_timeout = None;
+
+        if let Some(session) = self.session.as_mut() {
+            session.resume();
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        if self.countdown.is_paused() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    fn toggle_auto_mode(&mut self) {
+        self.auto_mode = !self.auto_mode;
+    }
+
+    /// Raises or lowers the volume by `change`, in steps of whole percents,
+    /// unmuting if muted.
+    fn change_volume(&mut self, change: f32) {
+        self.volume = ((self.volume + change) * 100.0).round().clamp(0.0, 100.0) / 100.0;
+        self.muted = false;
+        self.save_state();
+    }
+
+    fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+
+        if self.muted {
+            self.silence();
+        }
+
+        self.save_state();
+    }
+
+    /// Plays the break sound, so the volume can be tried out.
+    fn test_sound(&mut self) {
+        if self.muted {
+            self.message = Some("Sound is muted, press m to unmute".to_string());
+            return;
+        }
+
+        self.play_sound(Sound::BreakDue);
+    }
+
+    /// Remembers the volume for the next run.
+    fn save_state(&mut self) {
+        let state = State {
+            volume: Some(self.volume),
+            muted: self.muted,
+        };
+
+        if let Err(err) = state.save() {
+            self.message = Some(format!("Volume not saved: {err}"));
+        }
+    }
+
+    fn restore_state(&mut self, state: State) {
+        if let Some(volume) = state.volume {
+            self.volume = volume.clamp(0.0, 1.0);
+        }
+
+        self.muted = state.muted;
+    }
+
+    fn get_volume_string(&self) -> Option<String> {
+        if self.audio.is_silent() {
+            return None;
+        }
+
+        let volume = format!("{:.0}%", self.volume * 100.0);
+
+        if self.muted {
+            return Some(format!("Volume: muted ({volume})"));
+        }
+
+        return Some(format!("Volume: {volume}"));
+    }
+
+    fn load_notifiers(&mut self) {
+        match notify::notifiers(&self.config.alerts, !self.headless) {
+            Ok(notifiers) => self.notifiers = notifiers,
+            Err(err) => {
+                self.notifiers = Vec::new();
+                self.message = Some(format!("Alerts disabled: {err}"));
+            }
+        }
+    }
+
+    /// The alert for the end of the current phase.
+    fn phase_alert(&mut self) -> Alert {
+        self.alert += 1;
+
+        return match self.phase {
+            Phase::Work => {
+                let mut actions = Vec::new();
+
+                // in auto mode the break starts by itself
+                if !self.auto_mode {
+                    actions.push((Action::StartBreak, "Start break".to_string()));
+                }
+
+                if self.snoozes < self.config.snooze.max {
+                    actions.push((
+                        Action::Snooze,
+                        format!("Snooze {}", duration::format(self.config.snooze.length)),
+                    ));
+                }
+
+                let body = match self.snoozes {
+                    0 => "The work interval is over.".to_string(),
+                    1 => "The break has been snoozed once already.".to_string(),
+                    snoozes => format!("The break has been snoozed {snoozes} times already."),
+                };
+
+                Alert {
+                    id: self.alert,
+                    summary: "Time to stretch".to_string(),
+                    body,
+                    actions,
+                    urgent: self.escalation() > 0,
+                }
+            }
+            Phase::Break => Alert {
+                id: self.alert,
+                summary: "Break over".to_string(),
+                body: "Time to get back to work.".to_string(),
+                actions: Vec::new(),
+                urgent: false,
+            },
+        };
+    }
+
+    /// Plays the sound, louder after repeated snoozes, and tells every
+    /// notifier.
+    fn send_alert(&mut self, alert: &Alert, sound: Sound) {
+        match self.escalation() {
+            _ if self.muted => {}
+            0 => self.play_sound(sound),
+            level => self.audio.repeat(
+                sound,
+                (self.volume * 2f32.powi(level as i32)).min(1.0),
+                level + 1,
+            ),
+        }
+
+        for notifier in self.notifiers.iter_mut() {
+            if let Err(err) = notifier.notify(alert) {
+                self.message = Some(format!("Alert not sent: {err}"));
+            }
+        }
+    }
+
+    /// Sends the last alert again once the nag interval is over, until it is
+    /// acknowledged or has been repeated as often as allowed.
+    fn nag(&mut self) {
+        let (alert, sound, next, repeats) = match self.nagging.take() {
+            Some(nagging) => nagging,
+            None => return,
+        };
+
+        let nag = match &self.config.nag {
+            Some(nag) => nag.clone(),
+            None => return,
+        };
+
+        if !next.is_finished() {
+            self.nagging = Some((alert, sound, next, repeats));
+            return;
+        }
+
+        self.send_alert(&alert, sound);
+
+        if repeats + 1 < nag.max {
+            self.nagging = Some((alert, sound, Countdown::new(nag.interval), repeats + 1));
+        }
+    }
+
+    /// Stops repeating the last alert and takes it off the screen.
+    fn acknowledge(&mut self) {
+        self.nagging = None;
+
+        for notifier in self.notifiers.iter_mut() {
+            if let Err(err) = notifier.dismiss() {
+                self.message = Some(format!("Alert not dismissed: {err}"));
+            }
+        }
+    }
+
+    fn get_nag_string(&self) -> Option<String> {
+        let (_, _, next, _) = self.nagging.as_ref()?;
+
+        return Some(format!(
+            "Alert repeats in {}, press any key to stop it",
+            duration::format_hhmmss(next.remaining_seconds())
+        ));
+    }
+
+    fn show_status(&mut self) {
+        let status = format!(
+            "{} {} - stretchtime",
+            duration::format_hhmmss(self.countdown.remaining_seconds()),
+            self.get_title_string()
+        );
+
+        for notifier in self.notifiers.iter_mut() {
+            if let Err(err) = notifier.show_status(&status) {
+                self.message = Some(format!("Title not updated: {err}"));
+            }
+        }
+    }
+
+    /// Carries out actions picked from the last alert. Older alerts are stale
+    /// and ignored.
+    fn poll_notifiers(&mut self) {
+        let mut actions = Vec::new();
+
+        for notifier in self.notifiers.iter_mut() {
+            while let Some(response) = notifier.poll() {
+                if response.alert == self.alert {
+                    actions.push(response.action);
+                }
+            }
+        }
+
+        for action in actions {
+            self.acknowledge();
+
+            match action {
+                Action::StartBreak if self.phase == Phase::Work => self.next_phase(),
+                Action::StartBreak => {}
+                Action::Snooze => {
+                    if let Err(err) = self.snooze() {
+                        self.message = Some(err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Puts off the break that is due, or that began less than
+    /// `SNOOZE_WINDOW` ago, by the configured snooze length.
+    fn snooze(&mut self) -> std::result::Result<(), String> {
+        let max = self.config.snooze.max;
+
+        if self.snoozes >= max {
+            return Err(match max {
+                0 => "Snoozing is turned off".to_string(),
+                _ => format!("Already snoozed {max} times, time to stretch"),
+            });
+        }
+
+        match self.phase {
+            Phase::Work if !self.countdown.is_finished() => {
+                return Err("No break is due yet".to_string());
+            }
+            Phase::Work => {}
+            Phase::Break
+                if self.countdown.is_finished() || self.countdown.elapsed() >= SNOOZE_WINDOW =>
+            {
+                return Err("The break is already under way".to_string());
+            }
+            // the break is taken later, after the same work interval
+            Phase::Break => self.completed_intervals = self.completed_intervals.saturating_sub(1),
+        }
+
+        self.record(Event::Snoozed);
+        self.silence();
 
-struct App {
-    countdown_seconds: i32,
-    countdown_config: i32,
-    reset: bool,
-    auto_mode: bool,
-    sound_played: bool,
-    soloud: soloud::Soloud,
-    wav: soloud::audio::Wav,
-}
+        let length = self.config.snooze.length;
 
-impl App {
-    fn new(seconds: i32) -> App {
-        App {
-            countdown_seconds: seconds,
-            countdown_config: seconds,
-            reset: false,
-            auto_mode: false,
-            sound_played: false,
-            soloud: soloud::Soloud::default().unwrap(),
-            wav: soloud::audio::Wav::default(),
+        self.snoozes += 1;
+        self.phase = Phase::Work;
+        self.countdown = Countdown::new(length);
+        self.pause_timeout = None;
+        self.session = None;
+        self.sound_played = false;
+        self.message = Some(format!(
+            "Break snoozed for {} ({} of {max})",
+            duration::format(length),
+            self.snoozes
+        ));
+
+        return Ok(());
+    }
+
+    /// How far alerts are escalated after repeated snoozes, from 0 for a
+    /// normal alert. Each level doubles the volume and plays the chime once
+    /// more. Only the break that is due escalates, not the break itself.
+    fn escalation(&self) -> u32 {
+        let after = self.config.snooze.escalate_after;
+
+        if self.phase != Phase::Work || after == 0 || self.snoozes < after {
+            return 0;
         }
+
+        return (self.snoozes - after + 1).min(4);
     }
 
-    fn on_tick(&mut self) {
-        if self.countdown_seconds == 0 && !self.sound_played {
-            self.play_sound();
-            self.sound_played = true;
+    fn get_snooze_string(&self) -> Option<String> {
+        if self.phase != Phase::Work || self.snoozes == 0 {
+            return None;
+        }
+
+        return Some(match self.snoozes {
+            1 => "Break snoozed once".to_string(),
+            snoozes => format!("Break snoozed {snoozes} times"),
+        });
+    }
+
+    /// Carries out a request from the control socket.
+    fn handle(&mut self, command: ipc::Command) -> ipc::Reply {
+        match command {
+            ipc::Command::Status => return Ok(Some(self.status())),
+            _ => self.acknowledge(),
         }
 
-        if self.countdown_seconds == 0 && self.auto_mode {
-            self.reset = true;
+        match command {
+            ipc::Command::Status | ipc::Command::Acknowledge => {}
+            ipc::Command::Pause => self.pause(),
+            ipc::Command::Resume => self.resume(),
+            ipc::Command::Reset => self.reset(),
+            ipc::Command::Snooze => self.snooze()?,
+            ipc::Command::Skip => self.next_phase(),
+            ipc::Command::SetInterval(interval) => self.set_interval(interval),
+            ipc::Command::ToggleAuto => self.toggle_auto_mode(),
         }
 
-        if self.reset {
-            self.countdown_seconds = self.countdown_config;
-            self.sound_played = false;
-            self.reset = false;
+        return Ok(None);
+    }
+
+    fn status(&self) -> ipc::Status {
+        return ipc::Status {
+            phase: self.phase.name().to_lowercase(),
+            title: self.get_title_string().to_string(),
+            remaining: self.countdown.remaining_seconds(),
+            duration: self.countdown.duration().as_secs(),
+            paused: self.countdown.is_paused(),
+            auto_mode: self.auto_mode,
+            cycle: self.get_cycle_string(),
+            exercise: self
+                .session
+                .as_ref()
+                .and_then(Session::current)
+                .map(|exercise| exercise.name.clone()),
+        };
+    }
+
+    fn play_sound(&mut self, sound: Sound) {
+        if self.muted {
+            return;
+        }
+
+        self.audio.play(sound, self.volume);
+    }
+
+    fn silence(&mut self) {
+        self.audio.stop();
+    }
+}
+
+/// Describes sounds that failed to load, if any, all of which are replaced by
+/// the built-in chime.
+fn sound_errors(errors: &[SoundError]) -> Option<String> {
+    if errors.is_empty() {
+        return None;
+    }
+
+    let errors: Vec<String> = errors.iter().map(SoundError::to_string).collect();
+
+    return Some(format!(
+        "{}; playing the built-in chime instead",
+        errors.join("; ")
+    ));
+}
+
+/// The day the cycle count belongs to, which starts at `reset` rather than at
+/// midnight.
+fn cycle_day(reset: NaiveTime) -> NaiveDate {
+    return day_of(Local::now().naive_local(), reset);
+}
+
+fn day_of(time: NaiveDateTime, reset: NaiveTime) -> NaiveDate {
+    if time.time() < reset {
+        return time.date().pred_opt().unwrap_or(time.date());
+    }
+
+    return time.date();
+}
+
+fn main() {
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("stretchtime: {err}");
+            eprintln!("Try `stretchtime --help` for more information.");
+            exit(cli::EXIT_USAGE);
         }
+    };
 
-        if self.countdown_seconds > 0 {
-            self.countdown_seconds -= 1;
+    let res = match args.command {
+        Command::Run(options) => run(args.config, options),
+        Command::Status(options) => print_status(options),
+        Command::Control(command) => control(command),
+        Command::Stats => show_stats(args.config),
+        Command::History => show_history(),
+        Command::Export(options) => export_history(options),
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            Ok(())
         }
+        Command::Version => {
+            println!("stretchtime {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+    };
+
+    if let Err(err) = res {
+        eprintln!("stretchtime: {err}");
+        exit(cli::EXIT_FAILURE);
     }
+}
 
-    fn get_hhmmss(&self) -> String {
-        let hours = self.countdown_seconds / 3600;
-        let minutes = self.countdown_seconds % 3600 / 60;
-        let seconds = self.countdown_seconds % 3600 % 60;
+fn show_history() -> Result<()> {
+    let records = History::open().load().map_err(Error::History)?;
 
-        return format!(
-            "{:02}:{:02}:{:02} ({})",
-            hours, minutes, seconds, self.countdown_seconds
+    for record in records {
+        let line = format!(
+            "{}  {:<9}  {:<10}  {:>8} of {:<8}  {}",
+            record.time.format("%Y-%m-%d %H:%M:%S"),
+            record.event.name(),
+            record.phase_name(),
+            duration::format(Duration::from_secs(record.elapsed)),
+            duration::format(Duration::from_secs(record.planned)),
+            record.exercises.join(", ")
         );
+        println!("{}", line.trim_end());
     }
 
-    fn get_automode_string(&self) -> String {
-        return format!("Auto mode: {}", self.auto_mode);
+    return Ok(());
+}
+
+fn print_status(options: cli::StatusOptions) -> Result<()> {
+    if !options.follow {
+        let status = ipc::Client::connect()
+            .and_then(|mut client| client.send(ipc::Command::Status))
+            .map_err(Error::Ipc)?;
+
+        println!("{}", status::render(status.as_ref(), &options.format));
+
+        return Ok(());
     }
 
-    fn reset(&mut self) {
-        self.reset = true
+    // keeps going while no instance is running, so that a status bar picks
+    // the timer up again after a restart
+    let mut client = None;
+    let mut last = None;
+
+    loop {
+        if client.is_none() {
+            client = ipc::Client::connect().ok();
+        }
+
+        let status = match client
+            .as_mut()
+            .map(|client| client.send(ipc::Command::Status))
+        {
+            Some(Ok(status)) => status,
+            _ => {
+                client = None;
+                None
+            }
+        };
+
+        let line = status::render(status.as_ref(), &options.format);
+
+        if last.as_ref() != Some(&line) {
+            // stop once whatever reads the output goes away
+            if writeln!(std::io::stdout(), "{line}").is_err() {
+                return Ok(());
+            }
+
+            last = Some(line);
+        }
+
+        thread::sleep(Duration::from_millis(500));
     }
+}
 
-    fn toggle_auto_mode(&mut self) {
-        self.auto_mode = !self.auto_mode;
+fn control(command: ipc::Command) -> Result<()> {
+    ipc::Client::connect()
+        .and_then(|mut client| client.send(command))
+        .map_err(Error::Ipc)?;
+
+    return Ok(());
+}
+
+fn export_history(options: cli::ExportOptions) -> Result<()> {
+    let records: Vec<history::Record> = History::open()
+        .load()
+        .map_err(Error::History)?
+        .into_iter()
+        .filter(|record| {
+            let date = record.time.naive_local().date();
+
+            options.from.is_none_or(|from| date >= from) && options.to.is_none_or(|to| date <= to)
+        })
+        .collect();
+
+    let path = match options.output {
+        Some(path) => path,
+        None => {
+            return export::write(&records, options.format, &mut std::io::stdout().lock())
+                .map_err(|err| Error::Export(None, err));
+        }
+    };
+
+    let error = |err| Error::Export(Some(path.clone()), err);
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&path).map_err(error)?);
+    export::write(&records, options.format, &mut file).map_err(error)?;
+
+    return file.flush().map_err(error);
+}
+
+fn show_stats(config_path: Option<PathBuf>) -> Result<()> {
+    let (config, _) = Config::find(config_path.as_deref()).map_err(Error::Config)?;
+    let records = History::open().load().map_err(Error::History)?;
+    let stats = Stats::new(
+        &records,
+        cycle_day(config.cycle_reset),
+        config.cycle_reset,
+        config.goals,
+    );
+
+    for line in stats.summary() {
+        println!("{line}");
     }
 
-    fn play_sound(&mut self) {
-        let handle = self.soloud.play(&self.wav);
-        self.soloud.set_volume(handle, 0.2f32);
+    println!();
 
-        while self.soloud.voice_count() > 0 {}
+    for (date, totals) in stats.recent(7) {
+        println!(
+            "{}  {:>3} breaks  {:>8}",
+            date.format("%a %Y-%m-%d"),
+            totals.breaks,
+            duration::format(Duration::from_secs(totals.stretching))
+        );
     }
+
+    return Ok(());
 }
 
-fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
+fn run(config_path: Option<PathBuf>, options: cli::Options) -> Result<()> {
+    let (mut config, config_path) = Config::find(config_path.as_deref()).map_err(Error::Config)?;
+    config.merge(&options);
+
+    let library = Library::load().map_err(Error::Library)?;
+
+    if let Some(name) = &config.routine {
+        if library.routine(name).is_none() {
+            return Err(Error::UnknownRoutine(name.clone()));
+        }
+    }
+
+    // load the sounds into memory
+    let (audio, errors) = Audio::new(&config);
+
+    let daemon = options.daemon;
+    let mut app = App::new(config, library, audio, History::open(), daemon);
+
+    if let Some(errors) = sound_errors(&errors) {
+        app.message = Some(errors);
+    }
+
+    // a volume given on the command line wins over the one set last time
+    match State::load() {
+        Ok(state) if options.volume.is_some() => app.muted = state.muted,
+        Ok(state) => app.restore_state(state),
+        Err(err) => app.message = Some(format!("Volume not restored: {err}")),
+    }
 
-    if args.len() != 2 {
-        println!("Requires only 1 argument: time in seconds");
-        exit(1);
+    let reloader = config_path.map(|path| Reloader::new(path, options));
+
+    if daemon {
+        let server = Server::bind().map_err(Error::Ipc)?;
+        return run_daemon(app, reloader, server);
     }
 
-    let time_seconds: i32 = match args[1].parse() {
-        Ok(n) => n,
-        Err(_) => {
-            println!("Failure parsing argument: it must be a number");
-            exit(1);
+    // a second instance would record every event in the history again
+    let server = match Server::bind() {
+        Ok(server) => Some(server),
+        Err(err @ IpcError::AlreadyRunning(_)) => return Err(Error::Ipc(err)),
+        Err(err) => {
+            app.message = Some(format!("Control socket not available: {err}"));
+            None
         }
     };
 
@@ -119,16 +1255,7 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend).map_err(|_| Error::Terminal)?;
 
-    // create app and run it
-    let mut app = App::new(time_seconds);
-
-    // load wav into memory
-    let _ = app
-        .wav
-        .load_mem(include_bytes!("../resources/chimes.wav"))
-        .unwrap();
-
-    let res = run_app(&mut terminal, app);
+    let res = run_app(&mut terminal, app, reloader, server);
 
     // restore terminal
     disable_raw_mode().map_err(|_| Error::RawMode)?;
@@ -141,41 +1268,170 @@ fn main() -> Result<()> {
 
     terminal.show_cursor().map_err(|_| Error::Terminal)?;
 
-    if let Err(err) = res {
-        println!("{err:?}");
+    return res.map_err(Error::Io);
+}
+
+/// Runs the timer without a terminal until a signal asks it to stop, writing
+/// messages and phase changes to standard error.
+fn run_daemon(mut app: App, mut reloader: Option<Reloader>, server: Server) -> Result<()> {
+    let stop = Arc::new(AtomicBool::new(false));
+
+    for signal in [SIGINT, SIGTERM, SIGHUP] {
+        signal_hook::flag::register(signal, Arc::clone(&stop)).map_err(Error::Signal)?;
+    }
+
+    if let Some(audio) = app.get_audio_string() {
+        eprintln!("stretchtime: {audio}");
+    }
+
+    app.record(Event::Started);
+
+    let mut phase = None;
+
+    while !stop.load(Ordering::Relaxed) {
+        reload(&mut app, &mut reloader);
+        serve(&mut app, Some(&server));
+        app.on_tick();
+
+        let title = app.get_title_string();
+        if phase != Some(title) {
+            phase = Some(title);
+            eprintln!(
+                "stretchtime: {title} started, {} left",
+                duration::format(Duration::from_secs(app.countdown.remaining_seconds()))
+            );
+        }
+
+        if let Some(message) = app.message.take() {
+            eprintln!("stretchtime: {message}");
+        }
+
+        thread::sleep(app.config.tick_rate);
+    }
+
+    app.quit();
+
+    return Ok(());
+}
+
+fn serve(app: &mut App, server: Option<&Server>) {
+    while let Some(request) = server.and_then(Server::poll) {
+        let reply = app.handle(request.command);
+        request.respond(reply);
     }
+}
 
-    Ok(())
+fn reload(app: &mut App, reloader: &mut Option<Reloader>) {
+    match reloader.as_mut().and_then(Reloader::poll) {
+        Some(Ok(config)) => app.apply_config(config),
+        Some(Err(err)) => app.message = Some(format!("Config not reloaded: {err}")),
+        None => {}
+    }
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> std::io::Result<()> {
-    let tick_rate = std::time::Duration::from_millis(1000);
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    mut reloader: Option<Reloader>,
+    server: Option<Server>,
+) -> std::io::Result<()> {
+    // recorded only now, so that failing to set up the terminal leaves no
+    // phase behind that never ends
+    app.record(Event::Started);
+
     let mut last_tick = std::time::Instant::now();
 
     loop {
         terminal.draw(|f| ui(f, &app))?;
 
+        // the countdown is computed from the clock, so ticking faster than once
+        // a second only keeps the display close to the real second boundaries
+        let tick_rate = app.config.tick_rate;
+
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| std::time::Duration::from_secs(0));
 
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if let KeyCode::Char('q') = key.code {
-                    return Ok(());
-                }
+            if let event::Event::Key(key) = event::read()? {
+                app.acknowledge();
 
-                if let KeyCode::Char('r') = key.code {
-                    app.reset();
-                }
+                if app.input.is_some() {
+                    app.on_input_key(key.code);
+                } else {
+                    if let KeyCode::Char('q') = key.code {
+                        app.quit();
+                        return Ok(());
+                    }
+
+                    if let KeyCode::Char('r') = key.code {
+                        app.reset();
+                    }
+
+                    if let KeyCode::Char('a') = key.code {
+                        app.toggle_auto_mode();
+                    }
+
+                    if let KeyCode::Char('s') = key.code {
+                        app.silence();
+                    }
+
+                    if let KeyCode::Char('p') = key.code {
+                        app.toggle_pause();
+                    }
+
+                    if let KeyCode::Char('i') = key.code {
+                        app.edit_interval();
+                    }
+
+                    if let KeyCode::Enter = key.code {
+                        app.next_phase();
+                    }
+
+                    if let KeyCode::Char('n') = key.code {
+                        app.next_exercise();
+                    }
+
+                    if let KeyCode::Char('b') = key.code {
+                        app.previous_exercise();
+                    }
+
+                    if let KeyCode::Char('k') = key.code {
+                        app.skip_exercise();
+                    }
+
+                    if let KeyCode::Char('z') = key.code {
+                        if let Err(err) = app.snooze() {
+                            app.message = Some(err);
+                        }
+                    }
+
+                    if let KeyCode::Tab = key.code {
+                        app.toggle_stats();
+                    }
 
-                if let KeyCode::Char('a') = key.code {
-                    app.toggle_auto_mode();
+                    if let KeyCode::Char('+' | '=') = key.code {
+                        app.change_volume(0.1);
+                    }
+
+                    if let KeyCode::Char('-') = key.code {
+                        app.change_volume(-0.1);
+                    }
+
+                    if let KeyCode::Char('m') = key.code {
+                        app.toggle_mute();
+                    }
+
+                    if let KeyCode::Char('t') = key.code {
+                        app.test_sound();
+                    }
                 }
             }
         }
 
         if last_tick.elapsed() >= tick_rate {
+            reload(&mut app, &mut reloader);
+            serve(&mut app, server.as_ref());
             app.on_tick();
             last_tick = std::time::Instant::now();
         }
@@ -183,6 +1439,10 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> std::io::Res
 }
 
 fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
+    if let Some(stats) = &app.stats {
+        return stats_ui(f, app, stats);
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([tui::layout::Constraint::Length(0)].as_ref())
@@ -190,15 +1450,95 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
 
     let title = app.get_hhmmss();
     let automode = app.get_automode_string();
-    let text = vec![
+    let mut text = vec![
         tui::text::Spans::from(title),
         tui::text::Spans::from(automode),
     ];
 
+    if let Some(volume) = app.get_volume_string() {
+        text.push(tui::text::Spans::from(volume));
+    }
+
+    if let Some(cycle) = app.get_cycle_string() {
+        text.push(tui::text::Spans::from(cycle));
+    }
+
+    if let Some((exercise, description, details)) = app.get_exercise_strings() {
+        text.push(tui::text::Spans::from(""));
+        text.push(tui::text::Spans::from(tui::text::Span::styled(
+            exercise,
+            tui::style::Style::default()
+                .fg(tui::style::Color::Cyan)
+                .add_modifier(tui::style::Modifier::BOLD),
+        )));
+        text.push(tui::text::Spans::from(description));
+        text.push(tui::text::Spans::from(tui::text::Span::styled(
+            details,
+            tui::style::Style::default().fg(tui::style::Color::DarkGray),
+        )));
+        text.push(tui::text::Spans::from(""));
+    }
+
+    if let Some(phase) = app.get_phase_string() {
+        text.push(tui::text::Spans::from(tui::text::Span::styled(
+            phase,
+            tui::style::Style::default().add_modifier(tui::style::Modifier::BOLD),
+        )));
+    }
+
+    if let Some(snoozed) = app.get_snooze_string() {
+        let style = match app.escalation() {
+            0 => tui::style::Style::default().fg(tui::style::Color::Yellow),
+            _ => tui::style::Style::default()
+                .fg(tui::style::Color::White)
+                .bg(tui::style::Color::Red)
+                .add_modifier(tui::style::Modifier::BOLD),
+        };
+
+        text.push(tui::text::Spans::from(tui::text::Span::styled(
+            snoozed, style,
+        )));
+    }
+
+    if let Some(nag) = app.get_nag_string() {
+        text.push(tui::text::Spans::from(tui::text::Span::styled(
+            nag,
+            tui::style::Style::default().fg(tui::style::Color::Yellow),
+        )));
+    }
+
+    if let Some(input) = app.get_input_string() {
+        text.push(tui::text::Spans::from(input));
+    } else if let Some(message) = &app.message {
+        text.push(tui::text::Spans::from(message.as_str()));
+    }
+
+    if let Some(audio) = app.get_audio_string() {
+        text.push(tui::text::Spans::from(tui::text::Span::styled(
+            audio,
+            tui::style::Style::default().fg(tui::style::Color::Yellow),
+        )));
+    }
+
+    if let Some(paused) = app.get_paused_string() {
+        text.push(tui::text::Spans::from(tui::text::Span::styled(
+            paused,
+            tui::style::Style::default()
+                .fg(tui::style::Color::Yellow)
+                .add_modifier(tui::style::Modifier::BOLD),
+        )));
+    }
+
+    let border = match app.escalation() {
+        0 => tui::style::Style::default(),
+        _ => tui::style::Style::default().fg(tui::style::Color::Red),
+    };
+
     let block = Block::default()
         .borders(Borders::ALL)
+        .border_style(border)
         .title(tui::text::Span::styled(
-            "",
+            app.get_title_string(),
             tui::style::Style::default()
                 .fg(tui::style::Color::Magenta)
                 .add_modifier(tui::style::Modifier::BOLD),
@@ -211,3 +1551,235 @@ fn ui<B: Backend>(f: &mut Frame<B>, app: &App) {
 
     f.render_widget(paragraph, chunks[0]);
 }
+
+fn stats_ui<B: Backend>(f: &mut Frame<B>, app: &App, stats: &Stats) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(tui::text::Span::styled(
+            "Statistics",
+            tui::style::Style::default()
+                .fg(tui::style::Color::Magenta)
+                .add_modifier(tui::style::Modifier::BOLD),
+        ));
+    let area = block.inner(f.size());
+    f.render_widget(block, f.size());
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                tui::layout::Constraint::Length(9),
+                tui::layout::Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let mut text: Vec<tui::text::Spans> = stats
+        .summary()
+        .into_iter()
+        .map(tui::text::Spans::from)
+        .collect();
+
+    if let Some(message) = &app.message {
+        text.push(tui::text::Spans::from(""));
+        text.push(tui::text::Spans::from(message.as_str()));
+    }
+
+    let paragraph = Paragraph::new(text)
+        .alignment(tui::layout::Alignment::Center)
+        .wrap(tui::widgets::Wrap { trim: true });
+    f.render_widget(paragraph, chunks[0]);
+
+    let days = stats.recent(7);
+    let labels: Vec<String> = days
+        .iter()
+        .map(|(date, _)| date.format("%a").to_string())
+        .collect();
+    let data: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(&days)
+        .map(|(label, (_, totals))| (label.as_str(), totals.breaks as u64))
+        .collect();
+
+    let chart = BarChart::default()
+        .block(Block::default().title("Breaks per day"))
+        .data(&data)
+        .bar_width(5)
+        .bar_gap(1)
+        .bar_style(tui::style::Style::default().fg(tui::style::Color::Cyan))
+        .value_style(
+            tui::style::Style::default()
+                .fg(tui::style::Color::Black)
+                .bg(tui::style::Color::Cyan),
+        );
+    f.render_widget(chart, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use notify::{NotifyError, Response};
+
+    use std::cell::RefCell;
+    use std::fs;
+    use std::rc::Rc;
+
+    /// What the stand-in notifier was asked to do, and the responses it has
+    /// yet to hand back, shared with the test.
+    #[derive(Default)]
+    struct Bus {
+        sent: Vec<u64>,
+        dismissed: u32,
+        responses: Vec<Response>,
+    }
+
+    /// Stands in for the desktop notifier, so that responses can be sent
+    /// without a session bus.
+    struct FakeNotifier(Rc<RefCell<Bus>>);
+
+    impl Notifier for FakeNotifier {
+        fn notify(&mut self, alert: &Alert) -> std::result::Result<(), NotifyError> {
+            self.0.borrow_mut().sent.push(alert.id);
+            return Ok(());
+        }
+
+        fn poll(&mut self) -> Option<Response> {
+            return self.0.borrow_mut().responses.pop();
+        }
+
+        fn dismiss(&mut self) -> std::result::Result<(), NotifyError> {
+            self.0.borrow_mut().dismissed += 1;
+            return Ok(());
+        }
+    }
+
+    /// An app whose break is due, with its alert sent to a fake notifier.
+    fn app_with_break_due(name: &str) -> (App, Rc<RefCell<Bus>>, PathBuf) {
+        return app_with_config_and_break_due(name, Config::default());
+    }
+
+    fn app_with_config_and_break_due(
+        name: &str,
+        config: Config,
+    ) -> (App, Rc<RefCell<Bus>>, PathBuf) {
+        let path = std::env::temp_dir().join(format!(
+            "stretchtime-test-{}-{name}.jsonl",
+            std::process::id()
+        ));
+        let mut app = App::new(
+            config,
+            Library::builtin(),
+            Audio::silent(),
+            History::at(path.clone()),
+            true,
+        );
+
+        let bus = Rc::new(RefCell::new(Bus::default()));
+        app.notifiers.push(Box::new(FakeNotifier(Rc::clone(&bus))));
+        app.countdown = Countdown::new(Duration::ZERO);
+        app.on_tick();
+
+        return (app, bus, path);
+    }
+
+    fn respond(bus: &Rc<RefCell<Bus>>, alert: u64, action: Action) {
+        bus.borrow_mut().responses.push(Response { alert, action });
+    }
+
+    #[test]
+    fn alert_is_sent_when_the_work_interval_ends() {
+        let (app, bus, path) = app_with_break_due("sent");
+
+        assert_eq!(bus.borrow().sent, vec![app.alert]);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn start_break_response_starts_the_break() {
+        let (mut app, bus, path) = app_with_break_due("start");
+
+        respond(&bus, app.alert, Action::StartBreak);
+        app.poll_notifiers();
+
+        assert!(app.phase == Phase::Break);
+        assert_eq!(bus.borrow().dismissed, 1);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn snooze_response_snoozes_the_break() {
+        let (mut app, bus, path) = app_with_break_due("snooze");
+
+        respond(&bus, app.alert, Action::Snooze);
+        app.poll_notifiers();
+
+        assert!(app.phase == Phase::Work);
+        assert_eq!(app.snoozes, 1);
+        assert!(!app.countdown.is_finished());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn responses_to_an_old_alert_are_ignored() {
+        let (mut app, bus, path) = app_with_break_due("stale");
+
+        respond(&bus, app.alert - 1, Action::StartBreak);
+        respond(&bus, app.alert + 1, Action::Snooze);
+        app.poll_notifiers();
+
+        assert!(app.phase == Phase::Work);
+        assert_eq!(app.snoozes, 0);
+        assert_eq!(bus.borrow().dismissed, 0);
+        assert!(bus.borrow().responses.is_empty());
+
+        let _ = fs::remove_file(path);
+    }
+
+    fn nagging_config(auto_mode: bool) -> Config {
+        return Config {
+            auto_mode,
+            nag: Some(config::Nag {
+                interval: Duration::ZERO,
+                max: 3,
+            }),
+            ..Config::default()
+        };
+    }
+
+    #[test]
+    fn alert_repeats_until_the_phase_changes() {
+        let (mut app, bus, path) = app_with_config_and_break_due("nag", nagging_config(false));
+
+        app.on_tick();
+        assert_eq!(bus.borrow().sent.len(), 2);
+
+        app.next_phase();
+        app.on_tick();
+        app.on_tick();
+
+        assert!(app.nagging.is_none());
+        assert_eq!(bus.borrow().sent.len(), 2);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn alert_does_not_repeat_once_auto_mode_starts_the_break() {
+        let (mut app, bus, path) = app_with_config_and_break_due("nag-auto", nagging_config(true));
+
+        assert!(app.phase == Phase::Break);
+
+        app.on_tick();
+        app.on_tick();
+
+        assert!(app.nagging.is_none());
+        assert_eq!(bus.borrow().sent.len(), 1);
+
+        let _ = fs::remove_file(path);
+    }
+}
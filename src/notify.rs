@@ -0,0 +1,346 @@
+use serde::Deserialize;
+
+use crossterm::terminal::SetTitle;
+
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Ways of telling the user that a phase is over, selected with `alerts` in
+/// the config file.
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// A notification sent to `org.freedesktop.Notifications` on the
+    /// session bus.
+    Desktop,
+    /// The terminal bell.
+    Bell,
+    /// An OSC 9 escape sequence, shown as a notification by terminals such
+    /// as iTerm2, kitty and Windows Terminal.
+    Osc9,
+    /// An OSC 777 escape sequence, shown as a notification by terminals
+    /// such as rxvt-unicode, foot and those based on VTE.
+    Osc777,
+    /// The remaining time in the terminal window title, and in the tmux
+    /// window name when running inside tmux.
+    Title,
+}
+
+impl Kind {
+    /// Whether the alert is written to the terminal the app runs in.
+    fn needs_terminal(self) -> bool {
+        return self != Kind::Desktop;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    StartBreak,
+    Snooze,
+}
+
+#[derive(Clone)]
+#[cfg_attr(not(feature = "notifications"), allow(dead_code))]
+pub struct Alert {
+    /// Identifies the alert, so that responses to an old one can be told
+    /// apart from responses to the current one.
+    pub id: u64,
+    pub summary: String,
+    pub body: String,
+    /// Actions offered to the user, with their labels.
+    pub actions: Vec<(Action, String)>,
+    /// Sent after the break was put off too often, to stand out more.
+    pub urgent: bool,
+}
+
+/// An action the user picked from an alert.
+pub struct Response {
+    pub alert: u64,
+    pub action: Action,
+}
+
+#[derive(Debug)]
+pub struct NotifyError(String);
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+pub trait Notifier {
+    fn notify(&mut self, alert: &Alert) -> Result<(), NotifyError>;
+
+    /// Takes the next action the user picked, if any.
+    fn poll(&mut self) -> Option<Response> {
+        return None;
+    }
+
+    /// Shows the state of the timer, called on every tick.
+    fn show_status(&mut self, _status: &str) -> Result<(), NotifyError> {
+        return Ok(());
+    }
+
+    /// Withdraws the last alert, once it was acknowledged.
+    fn dismiss(&mut self) -> Result<(), NotifyError> {
+        return Ok(());
+    }
+}
+
+/// Creates a notifier for each kind of alert, leaving out those written to
+/// the terminal when there is none.
+pub fn notifiers(kinds: &[Kind], terminal: bool) -> Result<Vec<Box<dyn Notifier>>, NotifyError> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+
+    for kind in kinds {
+        if kind.needs_terminal() && !terminal {
+            continue;
+        }
+
+        match kind {
+            Kind::Desktop => notifiers.push(desktop()?),
+            Kind::Bell => notifiers.push(Box::new(Bell)),
+            Kind::Osc9 => notifiers.push(Box::new(Osc9)),
+            Kind::Osc777 => notifiers.push(Box::new(Osc777)),
+            Kind::Title => notifiers.push(Box::new(Title::new())),
+        }
+    }
+
+    return Ok(notifiers);
+}
+
+struct Bell;
+
+impl Notifier for Bell {
+    fn notify(&mut self, alert: &Alert) -> Result<(), NotifyError> {
+        let rings = if alert.urgent { 3 } else { 1 };
+
+        return write_terminal(&"\x07".repeat(rings));
+    }
+}
+
+struct Osc9;
+
+impl Notifier for Osc9 {
+    fn notify(&mut self, alert: &Alert) -> Result<(), NotifyError> {
+        let message = format!("{}: {}", alert.summary, alert.body);
+
+        return write_terminal(&passthrough(&format!("\x1b]9;{}\x07", clean(&message))));
+    }
+}
+
+struct Osc777;
+
+impl Notifier for Osc777 {
+    fn notify(&mut self, alert: &Alert) -> Result<(), NotifyError> {
+        // fields are separated by semicolons, so there can be none in the title
+        let summary = clean(&alert.summary).replace(';', ",");
+        let sequence = format!("\x1b]777;notify;{summary};{}\x07", clean(&alert.body));
+
+        return write_terminal(&passthrough(&sequence));
+    }
+}
+
+/// Keeps the window title up to date, putting back the previous title when
+/// dropped on terminals that keep a stack of titles.
+struct Title {
+    last: String,
+}
+
+impl Title {
+    fn new() -> Title {
+        let _ = write_terminal("\x1b[22;0t");
+
+        Title {
+            last: String::new(),
+        }
+    }
+}
+
+impl Notifier for Title {
+    fn notify(&mut self, _alert: &Alert) -> Result<(), NotifyError> {
+        return Ok(());
+    }
+
+    fn show_status(&mut self, status: &str) -> Result<(), NotifyError> {
+        if status == self.last {
+            return Ok(());
+        }
+
+        self.last = status.to_string();
+
+        crossterm::execute!(io::stdout(), SetTitle(clean(status))).map_err(terminal_error)?;
+
+        if in_tmux() {
+            write_terminal(&format!("\x1bk{}\x1b\\", clean(status)))?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Drop for Title {
+    fn drop(&mut self) {
+        let _ = write_terminal("\x1b[23;0t");
+    }
+}
+
+fn write_terminal(sequence: &str) -> Result<(), NotifyError> {
+    let mut stdout = io::stdout();
+
+    return stdout
+        .write_all(sequence.as_bytes())
+        .and_then(|_| stdout.flush())
+        .map_err(terminal_error);
+}
+
+fn terminal_error(err: io::Error) -> NotifyError {
+    return NotifyError(format!("failed to write to the terminal: {err}"));
+}
+
+fn in_tmux() -> bool {
+    return env::var_os("TMUX").is_some();
+}
+
+/// Wraps an escape sequence so that tmux hands it on to the outer terminal,
+/// which needs `allow-passthrough` to be on in tmux 3.3 and later.
+fn passthrough(sequence: &str) -> String {
+    if !in_tmux() {
+        return sequence.to_string();
+    }
+
+    return format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+}
+
+/// Removes control characters, which would end the escape sequence early.
+fn clean(text: &str) -> String {
+    return text.chars().filter(|c| !c.is_control()).collect();
+}
+
+#[cfg(feature = "notifications")]
+fn desktop() -> Result<Box<dyn Notifier>, NotifyError> {
+    return Ok(Box::new(desktop::Desktop::new()));
+}
+
+#[cfg(not(feature = "notifications"))]
+fn desktop() -> Result<Box<dyn Notifier>, NotifyError> {
+    return Err(NotifyError(
+        "desktop notifications are not supported by this build".to_string(),
+    ));
+}
+
+#[cfg(feature = "notifications")]
+mod desktop {
+    use notify_rust::{ActionResponse, Notification, NotificationHandle, Timeout};
+
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+
+    use super::{Action, Alert, Notifier, NotifyError, Response};
+
+    /// Sends notifications over D-Bus. The bus is found through
+    /// `$DBUS_SESSION_BUS_ADDRESS`, so pointing it at a private bus, e.g. with
+    /// `dbus-run-session`, keeps notifications off the desktop.
+    ///
+    /// Only the last alert stays on screen: it is closed when the next one is
+    /// sent or when it is dismissed, which also ends the thread waiting for
+    /// its buttons to be clicked.
+    pub struct Desktop {
+        sender: Sender<Response>,
+        receiver: Receiver<Response>,
+        shown: Option<NotificationHandle>,
+    }
+
+    impl Desktop {
+        pub fn new() -> Desktop {
+            let (sender, receiver) = mpsc::channel();
+
+            Desktop {
+                sender,
+                receiver,
+                shown: None,
+            }
+        }
+    }
+
+    impl Notifier for Desktop {
+        fn notify(&mut self, alert: &Alert) -> Result<(), NotifyError> {
+            self.dismiss()?;
+
+            let mut notification = Notification::new();
+            notification
+                .appname("stretchtime")
+                .summary(&alert.summary)
+                .body(&alert.body);
+
+            for (action, label) in &alert.actions {
+                notification.action(key(*action), label);
+            }
+
+            if !alert.actions.is_empty() {
+                notification.timeout(Timeout::Never);
+            }
+
+            #[cfg(all(unix, not(target_os = "macos")))]
+            if alert.urgent {
+                notification.urgency(notify_rust::Urgency::Critical);
+            }
+
+            let handle = notification
+                .show()
+                .map_err(|err| NotifyError(format!("failed to send notification: {err}")))?;
+
+            let notification_id = handle.id();
+            self.shown = Some(handle);
+
+            if alert.actions.is_empty() {
+                return Ok(());
+            }
+
+            let id = alert.id;
+            let actions: Vec<Action> = alert.actions.iter().map(|(action, _)| *action).collect();
+            let sender = self.sender.clone();
+
+            // blocks until the notification is acted on or closed
+            thread::spawn(move || {
+                let _ = notify_rust::handle_action(notification_id, |response| {
+                    if let ActionResponse::Custom(name) = response {
+                        if let Some(action) =
+                            actions.into_iter().find(|action| key(*action) == *name)
+                        {
+                            let _ = sender.send(Response { alert: id, action });
+                        }
+                    }
+                });
+            });
+
+            return Ok(());
+        }
+
+        fn poll(&mut self) -> Option<Response> {
+            return self.receiver.try_recv().ok();
+        }
+
+        fn dismiss(&mut self) -> Result<(), NotifyError> {
+            if let Some(handle) = self.shown.take() {
+                handle.close();
+            }
+
+            return Ok(());
+        }
+    }
+
+    impl Drop for Desktop {
+        fn drop(&mut self) {
+            let _ = self.dismiss();
+        }
+    }
+
+    fn key(action: Action) -> &'static str {
+        match action {
+            Action::StartBreak => "start-break",
+            Action::Snooze => "snooze",
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Resolves an XDG base directory, falling back to `$HOME/<fallback>` when the
+/// variable is unset or not absolute, as the specification requires.
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(variable).map(PathBuf::from) {
+        if dir.is_absolute() {
+            return Some(dir);
+        }
+    }
+
+    return env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback));
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    return xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("stretchtime"));
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    return xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join("stretchtime"));
+}
+
+pub fn state_dir() -> Option<PathBuf> {
+    return xdg_dir("XDG_STATE_HOME", ".local/state").map(|dir| dir.join("stretchtime"));
+}
+
+/// `$XDG_RUNTIME_DIR/stretchtime`. The specification gives no fallback for the
+/// runtime directory, since it must be private to the user.
+pub fn runtime_dir() -> Option<PathBuf> {
+    return env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join("stretchtime"));
+}
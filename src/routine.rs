@@ -0,0 +1,214 @@
+use serde::Deserialize;
+
+use std::time::Duration;
+
+use crate::countdown::Countdown;
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Sides {
+    /// Done once, or with both sides at the same time.
+    #[default]
+    One,
+    /// Done once for each side, as two steps.
+    Both,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    #[default]
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Exercise {
+    pub name: String,
+    pub description: String,
+    pub duration: Duration,
+    pub repetitions: Option<u32>,
+    pub sides: Sides,
+    pub tags: Vec<String>,
+    pub difficulty: Difficulty,
+}
+
+impl Exercise {
+    /// Time the exercise takes, counting both sides for two-sided ones.
+    pub fn total_duration(&self) -> Duration {
+        return match self.sides {
+            Sides::One => self.duration,
+            Sides::Both => self.duration * 2,
+        };
+    }
+
+    /// Tags, difficulty and repetitions as a single line.
+    pub fn details(&self) -> String {
+        let mut details = self.tags.clone();
+        details.push(self.difficulty.name().to_string());
+
+        if let Some(repetitions) = self.repetitions {
+            details.push(format!("{repetitions} repetitions"));
+        }
+
+        return details.join(", ");
+    }
+}
+
+#[derive(Clone)]
+pub struct Routine {
+    pub name: String,
+    pub exercises: Vec<Exercise>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Pending,
+    Done,
+    Skipped,
+}
+
+/// Progress through a routine during a break, with a sub-timer per exercise.
+pub struct Session {
+    name: String,
+    /// The routine's exercises, with two-sided ones split into a step per side.
+    steps: Vec<Exercise>,
+    step: usize,
+    countdown: Countdown,
+    outcomes: Vec<Outcome>,
+}
+
+impl Session {
+    pub fn new(routine: Routine) -> Session {
+        let mut steps = Vec::new();
+
+        for exercise in routine.exercises {
+            if exercise.sides == Sides::One {
+                steps.push(exercise);
+                continue;
+            }
+
+            for side in ["left", "right"] {
+                let mut step = exercise.clone();
+                step.name = format!("{} ({side})", exercise.name);
+                steps.push(step);
+            }
+        }
+
+        let first = steps
+            .first()
+            .map_or(Duration::ZERO, |exercise| exercise.duration);
+        let outcomes = vec![Outcome::Pending; steps.len()];
+
+        Session {
+            name: routine.name,
+            steps,
+            step: 0,
+            countdown: Countdown::new(first),
+            outcomes,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn current(&self) -> Option<&Exercise> {
+        return self.steps.get(self.step);
+    }
+
+    /// One-based position of the current step and the number of steps.
+    pub fn position(&self) -> (usize, usize) {
+        return (self.step + 1, self.steps.len());
+    }
+
+    pub fn countdown(&self) -> &Countdown {
+        return &self.countdown;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        return self.step >= self.steps.len();
+    }
+
+    pub fn count(&self, outcome: Outcome) -> usize {
+        return self.outcomes.iter().filter(|o| **o == outcome).count();
+    }
+
+    /// Names of the exercises marked as done.
+    pub fn performed(&self) -> Vec<String> {
+        return self
+            .steps
+            .iter()
+            .zip(&self.outcomes)
+            .filter(|(_, outcome)| **outcome == Outcome::Done)
+            .map(|(exercise, _)| exercise.name.clone())
+            .collect();
+    }
+
+    /// Advances when the current exercise's timer runs out. Returns whether
+    /// the step changed.
+    pub fn on_tick(&mut self) -> bool {
+        if self.is_complete() || !self.countdown.is_finished() {
+            return false;
+        }
+
+        self.next();
+        return true;
+    }
+
+    /// Marks the current exercise as done and moves to the next one.
+    pub fn next(&mut self) {
+        self.finish_step(Outcome::Done);
+    }
+
+    /// Marks the current exercise as skipped and moves to the next one.
+    pub fn skip(&mut self) {
+        self.finish_step(Outcome::Skipped);
+    }
+
+    /// Goes back to the previous exercise and restarts its timer.
+    pub fn previous(&mut self) {
+        self.go_to(self.step.saturating_sub(1));
+    }
+
+    pub fn pause(&mut self) {
+        self.countdown.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.countdown.resume();
+    }
+
+    fn finish_step(&mut self, outcome: Outcome) {
+        if let Some(current) = self.outcomes.get_mut(self.step) {
+            *current = outcome;
+            self.go_to(self.step + 1);
+        }
+    }
+
+    fn go_to(&mut self, step: usize) {
+        let paused = self.countdown.is_paused();
+        let duration = self
+            .steps
+            .get(step)
+            .map_or(Duration::ZERO, |exercise| exercise.duration);
+
+        self.step = step;
+        self.countdown = Countdown::new(duration);
+
+        if paused {
+            self.countdown.pause();
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use serde::Deserialize;
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::library::Library;
+use crate::routine::{Exercise, Routine};
+
+/// How exercises are picked for a break when no routine is configured.
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Exercises done in this many previous breaks are avoided if possible.
+    pub avoid_recent: usize,
+    /// Fixed seed, for reproducible selections.
+    pub seed: Option<u64>,
+    /// Weights by tag or exercise name; a weight of 0 excludes an exercise.
+    #[serde(deserialize_with = "deserialize_preferences")]
+    pub preferences: BTreeMap<String, f64>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            avoid_recent: 2,
+            seed: None,
+            preferences: BTreeMap::new(),
+        }
+    }
+}
+
+pub struct Selector {
+    settings: Settings,
+    rng: Rng,
+    /// Exercises picked for each recent break, oldest first.
+    recent: VecDeque<Vec<String>>,
+    /// Position in the rotation across body areas, kept between breaks.
+    area: usize,
+}
+
+impl Selector {
+    pub fn new(settings: Settings) -> Selector {
+        let seed = settings.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        });
+
+        Selector {
+            settings,
+            rng: Rng::new(seed),
+            recent: VecDeque::new(),
+            area: 0,
+        }
+    }
+
+    /// Picks exercises that fit in `budget`, taking one body area at a time
+    /// and moving on to the next area for every pick. Exercises from recent
+    /// breaks are only used once the others run out.
+    pub fn select(&mut self, library: &Library, budget: Duration) -> Routine {
+        let candidates: Vec<&Exercise> = library
+            .exercises()
+            .iter()
+            .filter(|exercise| self.weight(exercise) > 0.0)
+            .collect();
+
+        let (recent, fresh): (Vec<&Exercise>, Vec<&Exercise>) =
+            candidates.into_iter().partition(|exercise| {
+                self.recent
+                    .iter()
+                    .any(|names| names.contains(&exercise.name))
+            });
+
+        let mut picked: Vec<Exercise> = Vec::new();
+        let mut remaining = budget;
+
+        self.fill(&fresh, &mut picked, &mut remaining);
+        self.fill(&recent, &mut picked, &mut remaining);
+
+        self.recent
+            .push_back(picked.iter().map(|e| e.name.clone()).collect());
+
+        while self.recent.len() > self.settings.avoid_recent {
+            self.recent.pop_front();
+        }
+
+        return Routine {
+            name: "Stretch break".to_string(),
+            exercises: picked,
+        };
+    }
+
+    fn fill(
+        &mut self,
+        candidates: &[&Exercise],
+        picked: &mut Vec<Exercise>,
+        remaining: &mut Duration,
+    ) {
+        let areas: Vec<&str> = candidates
+            .iter()
+            .flat_map(|exercise| areas_of(exercise))
+            .collect::<BTreeSet<&str>>()
+            .into_iter()
+            .collect();
+
+        loop {
+            let fits = |exercise: &&Exercise| {
+                exercise.total_duration() <= *remaining
+                    && !picked.iter().any(|p| p.name == exercise.name)
+            };
+
+            // the first area in the rotation with anything left that fits
+            let (area, choices) = (0..areas.len())
+                .map(|offset| (self.area + offset) % areas.len())
+                .map(|index| {
+                    let choices: Vec<&Exercise> = candidates
+                        .iter()
+                        .copied()
+                        .filter(|exercise| fits(exercise))
+                        .filter(|exercise| areas_of(exercise).contains(&areas[index]))
+                        .collect();
+
+                    (index, choices)
+                })
+                .find(|(_, choices)| !choices.is_empty())
+                .unwrap_or_default();
+
+            let exercise = match self.pick(&choices) {
+                Some(exercise) => exercise,
+                None => return,
+            };
+
+            self.area = area + 1;
+            *remaining -= exercise.total_duration();
+            picked.push(exercise.clone());
+        }
+    }
+
+    /// Product of the preference weights that apply to an exercise.
+    fn weight(&self, exercise: &Exercise) -> f64 {
+        return std::iter::once(&exercise.name)
+            .chain(exercise.tags.iter())
+            .filter_map(|key| self.settings.preferences.get(key))
+            .product();
+    }
+
+    fn pick<'a>(&mut self, choices: &[&'a Exercise]) -> Option<&'a Exercise> {
+        let total: f64 = choices.iter().map(|exercise| self.weight(exercise)).sum();
+
+        if choices.is_empty() || total <= 0.0 {
+            return None;
+        }
+
+        let mut target = self.rng.next_f64() * total;
+
+        for exercise in choices {
+            target -= self.weight(exercise);
+
+            if target < 0.0 {
+                return Some(exercise);
+            }
+        }
+
+        return choices.last().copied();
+    }
+}
+
+/// Body areas an exercise works on, with untagged exercises in an area of
+/// their own.
+fn areas_of(exercise: &Exercise) -> Vec<&str> {
+    if exercise.tags.is_empty() {
+        return vec![""];
+    }
+
+    return exercise.tags.iter().map(String::as_str).collect();
+}
+
+/// xorshift64*, kept here rather than pulled from a crate so that a seeded
+/// selection stays the same across releases.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+
+        Rng(if state == 0 { 1 } else { state })
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+
+        return x.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    }
+}
+
+fn deserialize_preferences<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, f64>, D::Error> {
+    let preferences = BTreeMap::<String, f64>::deserialize(deserializer)?;
+
+    if let Some((key, weight)) = preferences.iter().find(|(_, weight)| **weight < 0.0) {
+        return Err(serde::de::Error::custom(format!(
+            "preference `{key}` has negative weight {weight}"
+        )));
+    }
+
+    return Ok(preferences);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two exercises of 30 seconds for each of three areas.
+    fn library() -> Library {
+        let mut text = String::new();
+
+        for area in ["neck", "back", "wrists"] {
+            for number in 1..=2 {
+                text.push_str(&format!(
+                    "[[exercise]]\nname = \"{area} {number}\"\nduration = \"30s\"\ntags = [\"{area}\"]\n\n"
+                ));
+            }
+        }
+
+        return Library::parse(&text);
+    }
+
+    fn selector(preferences: &[(&str, f64)]) -> Selector {
+        return Selector::new(Settings {
+            avoid_recent: 2,
+            seed: Some(42),
+            preferences: preferences
+                .iter()
+                .map(|(key, weight)| (key.to_string(), *weight))
+                .collect(),
+        });
+    }
+
+    fn names(routine: &Routine) -> Vec<String> {
+        return routine
+            .exercises
+            .iter()
+            .map(|exercise| exercise.name.clone())
+            .collect();
+    }
+
+    #[test]
+    fn same_seed_picks_the_same_exercises() {
+        let library = library();
+        let (mut first, mut second) = (selector(&[]), selector(&[]));
+
+        for _ in 0..5 {
+            let budget = Duration::from_secs(60);
+            assert_eq!(
+                names(&first.select(&library, budget)),
+                names(&second.select(&library, budget))
+            );
+        }
+    }
+
+    #[test]
+    fn avoids_exercises_from_recent_breaks() {
+        let library = library();
+        let mut selector = selector(&[]);
+        let budget = Duration::from_secs(60);
+
+        let mut picked: Vec<String> = Vec::new();
+
+        for _ in 0..3 {
+            for name in names(&selector.select(&library, budget)) {
+                assert!(!picked.contains(&name), "{name} picked twice");
+                picked.push(name);
+            }
+        }
+
+        assert_eq!(picked.len(), 6);
+    }
+
+    #[test]
+    fn reuses_recent_exercises_once_the_others_run_out() {
+        let library = library();
+        let mut selector = selector(&[]);
+
+        let routine = selector.select(&library, Duration::from_secs(60));
+        let all = selector.select(&library, Duration::from_secs(180));
+
+        // the fresh exercises come first, then the recent ones
+        let mut recent = names(&all).split_off(4);
+        let mut expected = names(&routine);
+        recent.sort();
+        expected.sort();
+
+        assert_eq!(all.exercises.len(), 6);
+        assert_eq!(recent, expected);
+    }
+
+    #[test]
+    fn rotates_through_areas() {
+        let library = library();
+        let mut selector = selector(&[]);
+
+        for _ in 0..4 {
+            let routine = selector.select(&library, Duration::from_secs(90));
+            let mut areas: Vec<&String> = routine
+                .exercises
+                .iter()
+                .flat_map(|exercise| exercise.tags.iter())
+                .collect();
+            areas.sort();
+            areas.dedup();
+
+            assert_eq!(areas.len(), 3, "{:?}", names(&routine));
+        }
+    }
+
+    #[test]
+    fn excludes_exercises_weighted_zero() {
+        let library = library();
+        let mut selector = selector(&[("neck", 0.0), ("back 1", 0.0)]);
+
+        for _ in 0..5 {
+            let routine = selector.select(&library, Duration::from_secs(300));
+
+            assert_eq!(names(&routine).len(), 3);
+            assert!(routine
+                .exercises
+                .iter()
+                .all(|exercise| !exercise.tags.contains(&"neck".to_string())
+                    && exercise.name != "back 1"));
+        }
+    }
+
+    #[test]
+    fn stays_within_the_budget() {
+        let library = library();
+        let mut selector = selector(&[]);
+
+        for seconds in [30, 59, 61, 100, 150, 1000] {
+            let budget = Duration::from_secs(seconds);
+            let routine = selector.select(&library, budget);
+            let total: Duration = routine.exercises.iter().map(Exercise::total_duration).sum();
+
+            assert!(total <= budget);
+            assert_eq!(routine.exercises.len() as u64, (seconds / 30).min(6));
+        }
+    }
+
+    #[test]
+    fn picks_nothing_when_nothing_fits() {
+        let library = library();
+        let mut selector = selector(&[]);
+
+        assert!(selector
+            .select(&library, Duration::ZERO)
+            .exercises
+            .is_empty());
+        assert!(selector
+            .select(&library, Duration::from_secs(29))
+            .exercises
+            .is_empty());
+    }
+}
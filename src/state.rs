@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::paths;
+
+/// Settings changed while running that carry over to the next run, kept in
+/// `$XDG_STATE_HOME/stretchtime/state.toml`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    pub volume: Option<f32>,
+    pub muted: bool,
+}
+
+#[derive(Debug)]
+pub enum StateError {
+    NoStateDir,
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NoStateDir => {
+                write!(f, "no state directory; set $XDG_STATE_HOME or $HOME")
+            }
+            StateError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            StateError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+            StateError::Serialize(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+fn state_path() -> Result<PathBuf, StateError> {
+    return paths::state_dir()
+        .map(|dir| dir.join("state.toml"))
+        .ok_or(StateError::NoStateDir);
+}
+
+impl State {
+    /// Loads the saved state. A missing file is not an error, since there is
+    /// none until something is saved.
+    pub fn load() -> Result<State, StateError> {
+        let path = state_path()?;
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(State::default()),
+            Err(err) => return Err(StateError::Io(path, err)),
+        };
+
+        return toml::from_str(&text).map_err(|err| StateError::Parse(path, err));
+    }
+
+    pub fn save(&self) -> Result<(), StateError> {
+        let path = state_path()?;
+        let io_error = |err| StateError::Io(path.clone(), err);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+
+        let text = toml::to_string(self).map_err(StateError::Serialize)?;
+
+        return fs::write(&path, text).map_err(io_error);
+    }
+}
@@ -0,0 +1,414 @@
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::duration;
+use crate::history::{Event, Record};
+use crate::{day_of, Phase};
+
+#[derive(Clone, Copy, Default)]
+pub struct Totals {
+    /// Breaks that ran until their countdown reached zero.
+    pub breaks: u32,
+    /// Work intervals that ended, each one a prompt to take a break.
+    pub prompted: u32,
+    /// Time spent in breaks, in seconds.
+    pub stretching: u64,
+    /// Times a break that was due was put off.
+    pub snoozes: u32,
+}
+
+impl Totals {
+    fn add(&mut self, other: &Totals) {
+        self.breaks += other.breaks;
+        self.prompted += other.prompted;
+        self.stretching += other.stretching;
+        self.snoozes += other.snoozes;
+    }
+
+    /// Share of prompted breaks that were taken, if any were prompted.
+    pub fn compliance(&self) -> Option<f64> {
+        if self.prompted == 0 {
+            return None;
+        }
+
+        return Some((self.breaks as f64 / self.prompted as f64).min(1.0));
+    }
+
+    fn describe(&self) -> String {
+        let plural = if self.breaks == 1 { "" } else { "s" };
+
+        return format!(
+            "{} break{plural}, {} of stretching",
+            self.breaks,
+            duration::format(Duration::from_secs(self.stretching))
+        );
+    }
+}
+
+/// Daily goals. A day with no goal set counts towards a streak as soon as it
+/// has a break.
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Goals {
+    pub breaks: Option<u32>,
+    #[serde(deserialize_with = "deserialize_stretching")]
+    pub stretching: Option<Duration>,
+    /// Days on which missing the goal ends a streak. Other days only add to
+    /// it when the goal is met.
+    #[serde(deserialize_with = "deserialize_working_days")]
+    pub working_days: Vec<Weekday>,
+}
+
+impl Default for Goals {
+    fn default() -> Goals {
+        Goals {
+            breaks: None,
+            stretching: None,
+            working_days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+        }
+    }
+}
+
+impl Goals {
+    pub fn is_set(&self) -> bool {
+        return self.breaks.is_some() || self.stretching.is_some();
+    }
+
+    fn met(&self, totals: &Totals) -> bool {
+        if !self.is_set() {
+            return totals.breaks > 0;
+        }
+
+        let breaks = self.breaks.is_none_or(|goal| totals.breaks >= goal);
+        let stretching = self
+            .stretching
+            .is_none_or(|goal| totals.stretching >= goal.as_secs());
+
+        return breaks && stretching;
+    }
+
+    fn progress(&self, totals: &Totals) -> Option<String> {
+        let mut parts = Vec::new();
+
+        if let Some(goal) = self.breaks {
+            parts.push(format!("{} of {goal} breaks", totals.breaks));
+        }
+
+        if let Some(goal) = self.stretching {
+            parts.push(format!(
+                "{} of {} stretching",
+                duration::format(Duration::from_secs(totals.stretching)),
+                duration::format(goal)
+            ));
+        }
+
+        if parts.is_empty() {
+            return None;
+        }
+
+        let met = if self.met(totals) { " (met)" } else { "" };
+
+        return Some(format!("{}{met}", parts.join(", ")));
+    }
+}
+
+/// Per-day totals computed from the history, with days starting at the
+/// configured cycle reset time.
+pub struct Stats {
+    days: BTreeMap<NaiveDate, Totals>,
+    today: NaiveDate,
+    goals: Goals,
+}
+
+impl Stats {
+    pub fn new(records: &[Record], today: NaiveDate, reset: NaiveTime, goals: Goals) -> Stats {
+        let mut days: BTreeMap<NaiveDate, Totals> = BTreeMap::new();
+
+        for record in records {
+            let totals = days
+                .entry(day_of(record.time.naive_local(), reset))
+                .or_default();
+
+            match (record.phase, record.event) {
+                (_, Event::Started) => {}
+                (phase, Event::Snoozed) => {
+                    totals.snoozes += 1;
+
+                    if phase == Phase::Break {
+                        totals.stretching += record.elapsed;
+                    }
+                }
+                // a snooze ending is the same break coming due again
+                (Phase::Work, Event::Completed | Event::Skipped) if !record.snooze => {
+                    totals.prompted += 1
+                }
+                (Phase::Work, _) => {}
+                (Phase::Break, event) => {
+                    if event == Event::Completed {
+                        totals.breaks += 1;
+                    }
+                    totals.stretching += record.elapsed;
+                }
+            }
+        }
+
+        Stats { days, today, goals }
+    }
+
+    pub fn day(&self, date: NaiveDate) -> Totals {
+        return self.days.get(&date).copied().unwrap_or_default();
+    }
+
+    pub fn today(&self) -> Totals {
+        return self.day(self.today);
+    }
+
+    /// Totals since Monday.
+    pub fn week(&self) -> Totals {
+        let monday =
+            self.today - chrono::Duration::days(self.today.weekday().num_days_from_monday() as i64);
+        let mut totals = Totals::default();
+
+        for (_, day) in self.days.range(monday..=self.today) {
+            totals.add(day);
+        }
+
+        return totals;
+    }
+
+    /// The last `count` days, oldest first.
+    pub fn recent(&self, count: u32) -> Vec<(NaiveDate, Totals)> {
+        return (0..count as i64)
+            .rev()
+            .map(|offset| self.today - chrono::Duration::days(offset))
+            .map(|date| (date, self.day(date)))
+            .collect();
+    }
+
+    pub fn goal_met(&self) -> bool {
+        return self.goals.met(&self.today());
+    }
+
+    /// Days on which the goal was met, counting back from today, or from
+    /// yesterday while today's goal is not met yet. Days off are passed over
+    /// when the goal was not met on them.
+    pub fn streak(&self) -> u32 {
+        let first = match self.days.keys().next() {
+            Some(first) => *first,
+            None => return 0,
+        };
+
+        let mut date = self.today;
+
+        if !self.goal_met() {
+            date = date.pred_opt().unwrap_or(date);
+        }
+
+        let mut streak = 0;
+
+        while date >= first {
+            if self.goals.met(&self.day(date)) {
+                streak += 1;
+            } else if self.goals.working_days.contains(&date.weekday()) {
+                break;
+            }
+
+            date = match date.pred_opt() {
+                Some(previous) => previous,
+                None => break,
+            };
+        }
+
+        return streak;
+    }
+
+    pub fn summary(&self) -> Vec<String> {
+        let week = self.week();
+        let compliance = match week.compliance() {
+            Some(rate) => format!(
+                "{:.0}% ({} of {} breaks taken this week)",
+                rate * 100.0,
+                week.breaks.min(week.prompted),
+                week.prompted
+            ),
+            None => "no breaks prompted this week".to_string(),
+        };
+        let streak = self.streak();
+        let plural = if streak == 1 { "" } else { "s" };
+
+        let mut summary = vec![
+            format!("Today: {}", self.today().describe()),
+            format!("This week: {}", week.describe()),
+            format!("Compliance: {compliance}"),
+            format!("Snoozed: {} this week", times(week.snoozes)),
+        ];
+
+        if let Some(progress) = self.goals.progress(&self.today()) {
+            summary.push(format!("Goal: {progress}"));
+        }
+
+        summary.push(format!("Streak: {streak} day{plural}"));
+
+        return summary;
+    }
+}
+
+fn deserialize_stretching<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    return duration::deserialize(deserializer).map(Some);
+}
+
+fn deserialize_working_days<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Weekday>, D::Error> {
+    let names = Vec::<String>::deserialize(deserializer)?;
+
+    return names
+        .iter()
+        .map(|name| {
+            name.parse::<Weekday>().map_err(|_| {
+                de::Error::custom(format!("`{name}` is not a day of the week such as \"mon\""))
+            })
+        })
+        .collect();
+}
+
+fn times(count: u32) -> String {
+    return match count {
+        1 => "once".to_string(),
+        count => format!("{count} times"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{Local, TimeZone};
+
+    fn date(day: u32) -> NaiveDate {
+        // October 2026 starts on a Thursday, so the 12th is a Monday
+        return NaiveDate::from_ymd_opt(2026, 10, day).unwrap();
+    }
+
+    fn record(day: u32, phase: Phase, event: Event, elapsed: u64) -> Record {
+        let time = date(day).and_hms_opt(12, 0, 0).unwrap();
+
+        return Record {
+            time: Local.from_local_datetime(&time).unwrap(),
+            event,
+            phase,
+            long_break: false,
+            snooze: false,
+            planned: elapsed,
+            elapsed,
+            exercises: Vec::new(),
+        };
+    }
+
+    /// A break taken on each of the given days.
+    fn breaks(days: &[u32]) -> Vec<Record> {
+        return days
+            .iter()
+            .map(|day| record(*day, Phase::Break, Event::Completed, 300))
+            .collect();
+    }
+
+    fn stats(records: &[Record], today: u32, goals: Goals) -> Stats {
+        let reset = NaiveTime::from_hms_opt(4, 0, 0).unwrap();
+
+        return Stats::new(records, date(today), reset, goals);
+    }
+
+    #[test]
+    fn streak_passes_over_the_weekend() {
+        let stats = stats(&breaks(&[15, 16, 19]), 19, Goals::default());
+
+        assert_eq!(stats.streak(), 3);
+    }
+
+    #[test]
+    fn breaks_on_days_off_add_to_the_streak() {
+        let stats = stats(&breaks(&[16, 17, 19]), 19, Goals::default());
+
+        assert_eq!(stats.streak(), 3);
+    }
+
+    #[test]
+    fn missed_working_day_ends_the_streak() {
+        let stats = stats(&breaks(&[13, 14, 16]), 16, Goals::default());
+
+        assert_eq!(stats.streak(), 1);
+    }
+
+    #[test]
+    fn streak_counts_from_yesterday_until_today_is_met() {
+        let stats = stats(&breaks(&[15, 16]), 19, Goals::default());
+
+        assert!(!stats.goal_met());
+        assert_eq!(stats.streak(), 2);
+    }
+
+    #[test]
+    fn days_short_of_the_goal_do_not_count() {
+        let goals = Goals {
+            breaks: Some(2),
+            ..Goals::default()
+        };
+        let stats = stats(&breaks(&[14, 14, 15, 16, 16]), 16, goals);
+
+        assert!(stats.goal_met());
+        assert_eq!(stats.streak(), 1);
+    }
+
+    #[test]
+    fn goal_needs_both_breaks_and_stretching() {
+        let goals = Goals {
+            breaks: Some(1),
+            stretching: Some(Duration::from_secs(600)),
+            ..Goals::default()
+        };
+
+        let met = goals.met(&Totals {
+            breaks: 2,
+            stretching: 600,
+            ..Totals::default()
+        });
+        let short = goals.met(&Totals {
+            breaks: 2,
+            stretching: 599,
+            ..Totals::default()
+        });
+
+        assert!(met);
+        assert!(!short);
+    }
+
+    #[test]
+    fn snoozed_break_is_prompted_once() {
+        let mut records = vec![
+            record(16, Phase::Work, Event::Completed, 1200),
+            record(16, Phase::Work, Event::Snoozed, 0),
+            record(16, Phase::Work, Event::Completed, 300),
+            record(16, Phase::Break, Event::Completed, 300),
+        ];
+        records[2].snooze = true;
+
+        let today = stats(&records, 16, Goals::default()).today();
+
+        assert_eq!(today.prompted, 1);
+        assert_eq!(today.snoozes, 1);
+        assert_eq!(today.compliance(), Some(1.0));
+    }
+}
@@ -0,0 +1,134 @@
+use serde_json::json;
+
+use std::time::Duration;
+
+use crate::duration;
+use crate::ipc::Status;
+
+pub enum Format {
+    Text,
+    Json,
+    /// The custom module format of waybar, which polybar and i3blocks
+    /// scripts can also pick apart.
+    Waybar,
+    /// Text with `{field}` placeholders.
+    Template(String),
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Option<Format> {
+        match value {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "waybar" => Some(Format::Waybar),
+            _ if value.contains('{') => Some(Format::Template(value.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the status as one line, or what to show while no instance is
+/// running.
+pub fn render(status: Option<&Status>, format: &Format) -> String {
+    let status = match status {
+        Some(status) => status,
+        None => {
+            return match format {
+                Format::Json => "null".to_string(),
+                Format::Waybar => json!({
+                    "text": "",
+                    "tooltip": "stretchtime is not running",
+                    "class": "stopped",
+                })
+                .to_string(),
+                Format::Text | Format::Template(_) => String::new(),
+            };
+        }
+    };
+
+    match format {
+        Format::Text => text(status),
+        Format::Json => serde_json::to_string(status).unwrap_or_default(),
+        Format::Waybar => waybar(status),
+        Format::Template(template) => template_fields(status)
+            .iter()
+            .fold(template.clone(), |line, (name, value)| {
+                line.replace(&format!("{{{name}}}"), value)
+            }),
+    }
+}
+
+fn text(status: &Status) -> String {
+    let mut line = format!(
+        "{} {}",
+        status.title,
+        duration::format_hhmmss(status.remaining)
+    );
+
+    if let Some(cycle) = &status.cycle {
+        line.push_str(&format!(", {cycle}"));
+    }
+
+    if let Some(exercise) = &status.exercise {
+        line.push_str(&format!(", {exercise}"));
+    }
+
+    if status.auto_mode {
+        line.push_str(", auto mode");
+    }
+
+    if status.paused {
+        line.push_str(", paused");
+    }
+
+    return line;
+}
+
+fn waybar(status: &Status) -> String {
+    let mut class = vec![status.phase.clone()];
+
+    if status.paused {
+        class.push("paused".to_string());
+    }
+
+    if status.auto_mode {
+        class.push("auto".to_string());
+    }
+
+    let percentage = match status.duration {
+        0 => 0,
+        duration => (duration - status.remaining.min(duration)) * 100 / duration,
+    };
+
+    return json!({
+        "text": duration::format_hhmmss(status.remaining),
+        "alt": status.phase,
+        "tooltip": text(status),
+        "class": class,
+        "percentage": percentage,
+    })
+    .to_string();
+}
+
+fn template_fields(status: &Status) -> Vec<(&'static str, String)> {
+    return vec![
+        ("phase", status.phase.clone()),
+        ("title", status.title.clone()),
+        ("hhmmss", duration::format_hhmmss(status.remaining)),
+        (
+            "remaining",
+            duration::format(Duration::from_secs(status.remaining)),
+        ),
+        ("seconds", status.remaining.to_string()),
+        (
+            "paused",
+            if status.paused { "paused" } else { "" }.to_string(),
+        ),
+        (
+            "auto",
+            if status.auto_mode { "auto" } else { "" }.to_string(),
+        ),
+        ("cycle", status.cycle.clone().unwrap_or_default()),
+        ("exercise", status.exercise.clone().unwrap_or_default()),
+    ];
+}